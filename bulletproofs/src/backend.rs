@@ -0,0 +1,108 @@
+// import types from libaries and modules
+use std::time::Instant;
+use bulletproofs::{BulletproofGens, PedersenGens};
+use bulletproofs_r1cs::BulletproofGens as R1csGens;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use voting_core::backend::{Nullifier, ProofBackend, ProofMetrics};
+use voting_core::ballot::Ballot;
+use voting_core::error::VotingError;
+use crate::ballot_proof::BatchVerifier;
+use crate::election::Election;
+use crate::elgamal;
+use crate::vote_proof::{BallotProof, Vote};
+use crate::voter_registration::{Voter, VoterNullifier};
+use crate::{contest_proof, membership_proof};
+
+// public parameters of the bulletproofs backend for one election
+pub struct BulletproofParams {
+    pub bp_gens: BulletproofGens, // contest range proof generators
+    pub r1cs_gens: R1csGens, // constraint system proof generators
+    pub pc_gens: PedersenGens, // encryption generators under the election key
+    pub commit_gens: PedersenGens, // independent commitment generators for the range and membership proofs
+}
+
+// ballot proofs a voter can choose between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofKind {
+    OneHot, // bit proof per candidate and a proof the bits sum to one
+    Membership, // constraint system proof that the vote is a candidate index
+}
+
+// bulletproofs backend implementation
+impl ProofBackend for Vote {
+    type Setup = RistrettoPoint; // joint election key from the trustees
+    type Params = BulletproofParams;
+    type Witness = (Ballot, ProofKind);
+    type Opening = (); // the trustees decrypt, so the voter keeps nothing
+
+    // function to size the generators for the ballot and bind encryption to the election key
+    fn setup(election: &Election, public_key: RistrettoPoint) -> Result<BulletproofParams, VotingError> {
+        Ok(BulletproofParams {
+            bp_gens: BulletproofGens::new(election.contest_bits, contest_proof::gens_capacity(election.num_contests())),
+            r1cs_gens: R1csGens::new(membership_proof::gens_capacity(election.num_candidates()), 1),
+            pc_gens: elgamal::election_gens(public_key),
+            commit_gens: PedersenGens::default(),
+        })
+    }
+
+    // function to encrypt and prove a ballot under the voter's nullifier for this election
+    fn prove(params: &BulletproofParams, election: &Election, voter: &Voter, (ballot, kind): (Ballot, ProofKind)) -> Result<(Self, ()), VotingError> {
+        let nullifier = voter.nullifier(&election.id);
+        let vote = match kind {
+            ProofKind::OneHot => Vote::new(ballot.candidate, &ballot.answers, nullifier, election, params),
+            ProofKind::Membership => Vote::new_membership(ballot.candidate, &ballot.answers, nullifier, election, params),
+        }?;
+        Ok((vote, ()))
+    }
+
+    // function for proof verification
+    fn verify(&mut self, params: &BulletproofParams, election: &Election) -> Result<(), VotingError> {
+
+        // verification time initialization
+        let start_time = Instant::now();
+
+        // verify the proof
+        let mut batch = BatchVerifier::new();
+        let result = self.queue_verification(election, params, &mut batch) && batch.verify();
+
+        // measure verification time
+        self.verify_time = start_time.elapsed().as_nanos();
+        if result { Ok(()) } else { Err(VotingError::InvalidProof) }
+
+    }
+
+    // function to queue every proof into one randomized check
+    fn batch_verify(params: &BulletproofParams, election: &Election, votes: &[&Self]) -> bool {
+        let mut batch = BatchVerifier::new();
+        votes.iter().all(|vote| vote.queue_verification(election, params, &mut batch))
+            && batch.verify()
+    }
+
+    // function to serialize the ciphertexts and proofs, the constraint system proof in its own encoding
+    fn serialize(&self) -> Result<Vec<u8>, VotingError> {
+        let encode = |error: bincode::Error| VotingError::Serialization(error.to_string());
+        let mut bytes = bincode::serialize(&(&self.nullifier, &self.ciphertexts, &self.contest_ciphertexts, &self.contest_proof)).map_err(encode)?;
+        match &self.proof {
+            BallotProof::OneHot { bit_proofs, sum_proof } => {
+                bytes.extend(bincode::serialize(&(bit_proofs, sum_proof)).map_err(encode)?);
+            }
+            BallotProof::Membership { proof, commitments, equality_proofs } => {
+                bytes.extend(bincode::serialize(&(commitments, equality_proofs)).map_err(encode)?);
+                bytes.extend(proof.to_bytes());
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn nullifier(&self) -> Nullifier {
+        self.nullifier
+    }
+
+    fn metrics(&self) -> ProofMetrics {
+        ProofMetrics {
+            gen_time: self.gen_time,
+            verify_time: self.verify_time,
+            proof_size: self.proof_size,
+        }
+    }
+}
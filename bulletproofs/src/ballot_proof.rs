@@ -0,0 +1,338 @@
+// import types from libaries and modules
+use bulletproofs::PedersenGens;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use merlin::Transcript;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use crate::elgamal::Ciphertext;
+
+// transcript helpers shared by the ballot proofs
+pub trait TranscriptProtocol {
+    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto);
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar;
+}
+
+// implement the helpers on merlin transcripts
+impl TranscriptProtocol for Transcript {
+
+    // function to commit a group element to the transcript
+    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto) {
+        self.append_message(label, point.as_bytes());
+    }
+
+    // function to derive a fiat-shamir challenge
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        let mut bytes = [0u8; 64];
+        self.challenge_bytes(label, &mut bytes);
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+
+}
+
+// proof that a ciphertext encrypts either zero or one
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BitProof {
+    pub a0_g: CompressedRistretto, // announcement on B for the zero branch
+    pub a0_h: CompressedRistretto, // announcement on the election key for the zero branch
+    pub a1_g: CompressedRistretto, // announcement on B for the one branch
+    pub a1_h: CompressedRistretto, // announcement on the election key for the one branch
+    pub c0: Scalar, // challenge share for the zero branch
+    pub z0: Scalar, // response for the zero branch
+    pub z1: Scalar, // response for the one branch
+}
+
+// chaum-pedersen proof that log_g(x) == log_h(y)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DleqProof {
+    pub a_g: CompressedRistretto, // announcement on g
+    pub a_h: CompressedRistretto, // announcement on h
+    pub z: Scalar, // response
+}
+
+// proof that a pedersen commitment and a ciphertext hold the same message, with c1 carrying the randomness behind c2
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EqualityProof {
+    pub a1: CompressedRistretto, // announcement for the ephemeral key
+    pub a2: CompressedRistretto, // announcement for the masked message
+    pub a3: CompressedRistretto, // announcement for the commitment
+    pub z_m: Scalar, // response for the message
+    pub z_r: Scalar, // response for the encryption randomness
+    pub z_s: Scalar, // response for the commitment blinding
+}
+
+// accumulator for randomized batch verification of ballot proofs
+pub struct BatchVerifier {
+    scalars: Vec<Scalar>, // weighted coefficients of every queued equation
+    points: Vec<RistrettoPoint>, // points of every queued equation
+}
+
+// batch verifier implementation
+impl BatchVerifier {
+
+    // function to create an empty batch
+    pub fn new() -> Self {
+        Self {
+            scalars: Vec::new(),
+            points: Vec::new(),
+        }
+    }
+
+    // function to queue the check sum(s_i * P_i) == 0 under a random weight
+    fn push(&mut self, terms: &[(Scalar, RistrettoPoint)]) {
+        let weight = Scalar::random(&mut OsRng);
+        for (scalar, point) in terms {
+            self.scalars.push(weight * scalar);
+            self.points.push(*point);
+        }
+    }
+
+    // function to check every queued equation with one multiscalar multiplication
+    pub fn verify(&self) -> bool {
+        RistrettoPoint::vartime_multiscalar_mul(&self.scalars, &self.points).is_identity()
+    }
+
+}
+
+// function to decompress a list of announcements
+fn decompress<const N: usize>(points: [&CompressedRistretto; N]) -> Option<[RistrettoPoint; N]> {
+    let mut out = [RistrettoPoint::default(); N];
+    for (slot, point) in out.iter_mut().zip(points) {
+        *slot = point.decompress()?;
+    }
+    Some(out)
+}
+
+// bit proof implementation
+impl BitProof {
+
+    // function to prove a ciphertext holds a bit
+    pub fn prove(
+        transcript: &mut Transcript,
+        pc_gens: &PedersenGens,
+        ciphertext: &Ciphertext,
+        bit: bool,
+        randomness: &Scalar,
+    ) -> Self {
+        let mut rng = OsRng;
+
+        // statements for each branch; the prover only knows one of them
+        let statements = [ciphertext.c2, ciphertext.c2 - pc_gens.B];
+        let real = bit as usize;
+        let fake = 1 - real;
+
+        // simulate the branch the prover cannot open
+        let mut challenges = [Scalar::ZERO; 2];
+        let mut responses = [Scalar::ZERO; 2];
+        let mut announcements = [(RistrettoPoint::default(), RistrettoPoint::default()); 2];
+        challenges[fake] = Scalar::random(&mut rng);
+        responses[fake] = Scalar::random(&mut rng);
+        announcements[fake] = (
+            responses[fake] * pc_gens.B - challenges[fake] * ciphertext.c1,
+            responses[fake] * pc_gens.B_blinding - challenges[fake] * statements[fake],
+        );
+
+        // commit to the real branch
+        let nonce = Scalar::random(&mut rng);
+        announcements[real] = (nonce * pc_gens.B, nonce * pc_gens.B_blinding);
+
+        // split the fiat-shamir challenge between the branches
+        let (a0_g, a0_h) = (announcements[0].0.compress(), announcements[0].1.compress());
+        let (a1_g, a1_h) = (announcements[1].0.compress(), announcements[1].1.compress());
+        transcript.append_point(b"bit-a0-g", &a0_g);
+        transcript.append_point(b"bit-a0-h", &a0_h);
+        transcript.append_point(b"bit-a1-g", &a1_g);
+        transcript.append_point(b"bit-a1-h", &a1_h);
+        let challenge = transcript.challenge_scalar(b"bit-c");
+        challenges[real] = challenge - challenges[fake];
+        responses[real] = nonce + challenges[real] * randomness;
+
+        Self {
+            a0_g,
+            a0_h,
+            a1_g,
+            a1_h,
+            c0: challenges[0],
+            z0: responses[0],
+            z1: responses[1],
+        }
+
+    }
+
+    // function to queue the bit checks into a batch, false if the proof is malformed
+    pub fn queue(
+        &self,
+        transcript: &mut Transcript,
+        pc_gens: &PedersenGens,
+        ciphertext: &Ciphertext,
+        batch: &mut BatchVerifier,
+    ) -> bool {
+
+        // recover the announcements
+        let Some([a0_g, a0_h, a1_g, a1_h]) = decompress([&self.a0_g, &self.a0_h, &self.a1_g, &self.a1_h]) else {
+            return false;
+        };
+
+        // recompute the challenge split
+        transcript.append_point(b"bit-a0-g", &self.a0_g);
+        transcript.append_point(b"bit-a0-h", &self.a0_h);
+        transcript.append_point(b"bit-a1-g", &self.a1_g);
+        transcript.append_point(b"bit-a1-h", &self.a1_h);
+        let challenge = transcript.challenge_scalar(b"bit-c");
+        let c1 = challenge - self.c0;
+
+        // each branch shows the same randomness behind c1 and c2 - bit * B
+        batch.push(&[(self.z0, pc_gens.B), (-Scalar::ONE, a0_g), (-self.c0, ciphertext.c1)]);
+        batch.push(&[(self.z0, pc_gens.B_blinding), (-Scalar::ONE, a0_h), (-self.c0, ciphertext.c2)]);
+        batch.push(&[(self.z1, pc_gens.B), (-Scalar::ONE, a1_g), (-c1, ciphertext.c1)]);
+        batch.push(&[(self.z1, pc_gens.B_blinding), (-Scalar::ONE, a1_h), (-c1, ciphertext.c2 - pc_gens.B)]);
+        true
+
+    }
+
+}
+
+// discrete log equality proof implementation
+impl DleqProof {
+
+    // function to prove x = secret * g and y = secret * h
+    pub fn prove(
+        transcript: &mut Transcript,
+        g: &RistrettoPoint,
+        h: &RistrettoPoint,
+        secret: &Scalar,
+    ) -> Self {
+        let mut rng = OsRng;
+
+        // commit to a nonce on both bases
+        let nonce = Scalar::random(&mut rng);
+        let a_g = (nonce * g).compress();
+        let a_h = (nonce * h).compress();
+        transcript.append_point(b"dleq-a-g", &a_g);
+        transcript.append_point(b"dleq-a-h", &a_h);
+        let challenge = transcript.challenge_scalar(b"dleq-c");
+
+        Self {
+            a_g,
+            a_h,
+            z: nonce + challenge * secret,
+        }
+
+    }
+
+    // function to verify x and y share a discrete log over g and h
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        (g, x): (&RistrettoPoint, &RistrettoPoint),
+        (h, y): (&RistrettoPoint, &RistrettoPoint),
+    ) -> bool {
+        let mut batch = BatchVerifier::new();
+        self.queue(transcript, (g, x), (h, y), &mut batch) && batch.verify()
+    }
+
+    // function to queue the equality checks into a batch, false if the proof is malformed
+    pub fn queue(
+        &self,
+        transcript: &mut Transcript,
+        (g, x): (&RistrettoPoint, &RistrettoPoint),
+        (h, y): (&RistrettoPoint, &RistrettoPoint),
+        batch: &mut BatchVerifier,
+    ) -> bool {
+
+        // recover the announcements
+        let Some([a_g, a_h]) = decompress([&self.a_g, &self.a_h]) else {
+            return false;
+        };
+
+        // recompute the challenge
+        transcript.append_point(b"dleq-a-g", &self.a_g);
+        transcript.append_point(b"dleq-a-h", &self.a_h);
+        let challenge = transcript.challenge_scalar(b"dleq-c");
+
+        // defer both checks to the batch
+        batch.push(&[(self.z, *g), (-Scalar::ONE, a_g), (-challenge, *x)]);
+        batch.push(&[(self.z, *h), (-Scalar::ONE, a_h), (-challenge, *y)]);
+        true
+
+    }
+
+}
+
+// equality proof implementation
+impl EqualityProof {
+
+    // function to prove knowledge of (m, r, s) with c1 = r * B, c2 = m * B + r * PK and commitment = m * B + s * H
+    pub fn prove(
+        transcript: &mut Transcript,
+        (pc_gens, commit_gens): (&PedersenGens, &PedersenGens),
+        value: &Scalar,
+        randomness: &Scalar,
+        blinding: &Scalar,
+    ) -> Self {
+        let mut rng = OsRng;
+
+        // commit to a nonce for each secret, sharing the message nonce between ciphertext and commitment
+        let nonce_m = Scalar::random(&mut rng);
+        let nonce_r = Scalar::random(&mut rng);
+        let nonce_s = Scalar::random(&mut rng);
+        let a1 = (nonce_r * pc_gens.B).compress();
+        let a2 = pc_gens.commit(nonce_m, nonce_r).compress();
+        let a3 = commit_gens.commit(nonce_m, nonce_s).compress();
+        transcript.append_point(b"eq-a1", &a1);
+        transcript.append_point(b"eq-a2", &a2);
+        transcript.append_point(b"eq-a3", &a3);
+        let challenge = transcript.challenge_scalar(b"eq-c");
+
+        Self {
+            a1,
+            a2,
+            a3,
+            z_m: nonce_m + challenge * value,
+            z_r: nonce_r + challenge * randomness,
+            z_s: nonce_s + challenge * blinding,
+        }
+
+    }
+
+    // function to queue the equality checks into a batch, false if the proof is malformed
+    pub fn queue(
+        &self,
+        transcript: &mut Transcript,
+        (pc_gens, commit_gens): (&PedersenGens, &PedersenGens),
+        ciphertext: &Ciphertext,
+        commitment: &RistrettoPoint,
+        batch: &mut BatchVerifier,
+    ) -> bool {
+
+        // recover the announcements
+        let Some([a1, a2, a3]) = decompress([&self.a1, &self.a2, &self.a3]) else {
+            return false;
+        };
+
+        // recompute the challenge
+        transcript.append_point(b"eq-a1", &self.a1);
+        transcript.append_point(b"eq-a2", &self.a2);
+        transcript.append_point(b"eq-a3", &self.a3);
+        let challenge = transcript.challenge_scalar(b"eq-c");
+
+        // the same randomness sits behind both components and the same message behind c2 and the commitment
+        batch.push(&[(self.z_r, pc_gens.B), (-Scalar::ONE, a1), (-challenge, ciphertext.c1)]);
+        batch.push(&[
+            (self.z_m, pc_gens.B),
+            (self.z_r, pc_gens.B_blinding),
+            (-Scalar::ONE, a2),
+            (-challenge, ciphertext.c2),
+        ]);
+        batch.push(&[
+            (self.z_m, commit_gens.B),
+            (self.z_s, commit_gens.B_blinding),
+            (-Scalar::ONE, a3),
+            (-challenge, *commitment),
+        ]);
+        true
+
+    }
+
+}
@@ -0,0 +1,66 @@
+// import types from libaries and modules
+use bulletproofs::{BulletproofGens, PedersenGens, ProofError, RangeProof};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::rngs::OsRng;
+
+// function to size the generators for a given number of contests
+pub fn gens_capacity(num_contests: usize) -> usize {
+    // aggregated range proofs need a power of two number of values
+    num_contests.max(1).next_power_of_two()
+}
+
+// function to prove every contest answer lies in [0, 2^bits) with one aggregated proof
+pub fn prove(
+    transcript: &mut Transcript,
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    answers: &[u64],
+    bits: usize,
+) -> Result<(RangeProof, Vec<CompressedRistretto>, Vec<Scalar>), ProofError> {
+    let mut rng = OsRng;
+
+    // pad the answers with zeros up to the aggregation size
+    let mut values = answers.to_vec();
+    values.resize(gens_capacity(answers.len()), 0);
+    let blindings: Vec<Scalar> = values.iter().map(|_| Scalar::random(&mut rng)).collect();
+
+    // proof generation
+    let (proof, commitments) = RangeProof::prove_multiple(
+        bp_gens,
+        pc_gens,
+        transcript,
+        &values,
+        &blindings,
+        bits,
+    )?;
+    Ok((proof, commitments, blindings))
+
+}
+
+// function to verify the aggregated contest proof
+pub fn verify(
+    proof: &RangeProof,
+    transcript: &mut Transcript,
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    commitments: &[CompressedRistretto],
+    bits: usize,
+) -> Result<(), ProofError> {
+    proof.verify_multiple(bp_gens, pc_gens, transcript, commitments, bits)
+}
+
+// function to measure the aggregated proof size for a number of contests
+pub fn proof_size(num_contests: usize, bits: usize) -> Option<usize> {
+
+    // fresh generators large enough for this many contests
+    let bp_gens = BulletproofGens::new(bits, gens_capacity(num_contests));
+    let pc_gens = PedersenGens::default();
+    let mut transcript = Transcript::new(b"ContestProofSize");
+
+    let answers = vec![0; num_contests];
+    let (proof, _, _) = prove(&mut transcript, &bp_gens, &pc_gens, &answers, bits).ok()?;
+    bincode::serialized_size(&proof).ok().map(|size| size as usize)
+
+}
@@ -0,0 +1,344 @@
+// import types from libaries and modules
+//
+// joint feldman distributed key generation: every trustee deals a feldman verifiable
+// secret sharing of a random secret, bad shares are disputed through public complaints,
+// and the election key is the sum of the secrets of every dealer left qualified
+use std::collections::{BTreeSet, HashMap};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use voting_core::error::VotingError;
+use crate::ballot_proof::TranscriptProtocol;
+use crate::threshold::{ElectionKey, Trustee};
+
+// broadcast commitments to a dealer's sharing polynomial
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Commitments {
+    pub dealer: u64, // trustee dealing the polynomial
+    pub points: Vec<RistrettoPoint>, // a_k * B for every coefficient
+    pub proof_a: CompressedRistretto, // announcement proving knowledge of a_0
+    pub proof_z: Scalar, // response proving knowledge of a_0
+}
+
+// private share sent from a dealer to one recipient
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Share {
+    pub dealer: u64, // trustee that evaluated the polynomial
+    pub recipient: u64, // trustee the share is for
+    pub value: Scalar, // polynomial evaluated at the recipient index
+}
+
+// broadcast complaint against a dealer whose share failed verification
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Complaint {
+    pub dealer: u64, // accused dealer
+    pub accuser: u64, // trustee that received the bad share
+}
+
+// broadcast answer to a complaint, revealing the disputed share
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Justification {
+    pub dealer: u64, // accused dealer
+    pub recipient: u64, // trustee that complained
+    pub value: Scalar, // share the dealer claims to have sent
+}
+
+// trustee taking part in the ceremony
+pub struct Participant {
+    pub index: u64, // position on every sharing polynomial, starting at one
+    threshold: usize, // number of trustees needed to decrypt
+    num_participants: usize, // number of trustees in the ceremony
+    coefficients: Vec<Scalar>, // own secret sharing polynomial
+    commitments: HashMap<u64, Commitments>, // valid commitments received from each dealer
+    shares: HashMap<u64, Scalar>, // valid shares received from each dealer
+}
+
+// function to evaluate a polynomial at a trustee index
+fn evaluate(coefficients: &[Scalar], index: u64) -> Scalar {
+    let x = Scalar::from(index);
+    coefficients.iter().rev().fold(Scalar::ZERO, |acc, coefficient| acc * x + coefficient)
+}
+
+// function to evaluate committed coefficients at a trustee index, giving share * B
+fn evaluate_commitments(points: &[RistrettoPoint], index: u64) -> RistrettoPoint {
+    let x = Scalar::from(index);
+    points.iter().rev().fold(RistrettoPoint::default(), |acc, point| acc * x + point)
+}
+
+// function to start the transcript of a dealer's proof of knowledge
+fn commitment_transcript(dealer: u64, points: &[RistrettoPoint]) -> Transcript {
+    let mut transcript = Transcript::new(b"DkgCommitments");
+    transcript.append_u64(b"dealer", dealer);
+    for point in points {
+        transcript.append_point(b"coefficient", &point.compress());
+    }
+    transcript
+}
+
+// commitments implementation
+impl Commitments {
+
+    // function to check the commitments are well formed for a ceremony
+    pub fn verify(&self, threshold: usize, num_participants: usize) -> bool {
+
+        // the dealer must be a trustee and commit to a polynomial of the agreed degree
+        if self.dealer == 0 || self.dealer > num_participants as u64 || self.points.len() != threshold {
+            return false;
+        }
+
+        // schnorr proof the dealer knows their secret, so it cannot be chosen after seeing the others
+        let Some(a) = self.proof_a.decompress() else {
+            return false;
+        };
+        let mut transcript = commitment_transcript(self.dealer, &self.points);
+        transcript.append_point(b"pok-a", &self.proof_a);
+        let challenge = transcript.challenge_scalar(b"pok-c");
+        self.proof_z * RISTRETTO_BASEPOINT_POINT == a + challenge * self.points[0]
+
+    }
+
+    // function to check a share against these commitments
+    pub fn verify_share(&self, recipient: u64, value: &Scalar) -> bool {
+        value * RISTRETTO_BASEPOINT_POINT == evaluate_commitments(&self.points, recipient)
+    }
+
+}
+
+// participant implementation
+impl Participant {
+
+    // function to join a ceremony with a fresh random polynomial
+    pub fn new(index: u64, threshold: usize, num_participants: usize) -> Result<Self, VotingError> {
+        let mut rng = OsRng;
+
+        // any threshold from one to every trustee is allowed
+        if threshold == 0 || threshold > num_participants {
+            return Err(VotingError::SetupFailed(format!("threshold {} of {} trustees", threshold, num_participants)));
+        }
+        if index == 0 || index > num_participants as u64 {
+            return Err(VotingError::SetupFailed(format!("trustee index {} of {} trustees", index, num_participants)));
+        }
+
+        Ok(Self {
+            index,
+            threshold,
+            num_participants,
+            coefficients: (0..threshold).map(|_| Scalar::random(&mut rng)).collect(),
+            commitments: HashMap::new(),
+            shares: HashMap::new(),
+        })
+
+    }
+
+    // function to build the commitments this participant broadcasts in round one
+    pub fn commitments(&self) -> Commitments {
+        let mut rng = OsRng;
+
+        // commit to every coefficient
+        let points: Vec<RistrettoPoint> = self.coefficients.iter()
+            .map(|coefficient| coefficient * RISTRETTO_BASEPOINT_POINT)
+            .collect();
+
+        // prove knowledge of the constant term
+        let nonce = Scalar::random(&mut rng);
+        let proof_a = (nonce * RISTRETTO_BASEPOINT_POINT).compress();
+        let mut transcript = commitment_transcript(self.index, &points);
+        transcript.append_point(b"pok-a", &proof_a);
+        let challenge = transcript.challenge_scalar(b"pok-c");
+
+        Commitments {
+            dealer: self.index,
+            points,
+            proof_a,
+            proof_z: nonce + challenge * self.coefficients[0],
+        }
+
+    }
+
+    // function to build the private shares this participant sends in round one
+    pub fn shares(&self) -> Vec<Share> {
+        (1..=self.num_participants as u64)
+            .map(|recipient| Share {
+                dealer: self.index,
+                recipient,
+                value: evaluate(&self.coefficients, recipient),
+            })
+            .collect()
+    }
+
+    // function to accept a dealer's broadcast commitments, false if they are malformed
+    pub fn receive_commitments(&mut self, commitments: &Commitments) -> bool {
+        if !commitments.verify(self.threshold, self.num_participants) {
+            return false;
+        }
+        self.commitments.insert(commitments.dealer, commitments.clone());
+        true
+    }
+
+    // function to accept a private share, returning a complaint if it fails verification
+    pub fn receive_share(&mut self, share: &Share) -> Option<Complaint> {
+
+        // shares addressed to someone else are ignored
+        if share.recipient != self.index {
+            return None;
+        }
+
+        // the share must match the dealer's broadcast commitments
+        match self.commitments.get(&share.dealer) {
+            Some(commitments) if commitments.verify_share(self.index, &share.value) => {
+                self.shares.insert(share.dealer, share.value);
+                None
+            }
+            _ => Some(Complaint { dealer: share.dealer, accuser: self.index }),
+        }
+
+    }
+
+    // function to answer a complaint against this participant by revealing the disputed share
+    pub fn justify(&self, complaint: &Complaint) -> Option<Justification> {
+        (complaint.dealer == self.index).then(|| Justification {
+            dealer: self.index,
+            recipient: complaint.accuser,
+            value: evaluate(&self.coefficients, complaint.accuser),
+        })
+    }
+
+    // function to adopt a publicly revealed share addressed to this participant
+    pub fn receive_justification(&mut self, justification: &Justification) {
+        if justification.recipient != self.index {
+            return;
+        }
+        if let Some(commitments) = self.commitments.get(&justification.dealer)
+            && commitments.verify_share(self.index, &justification.value) {
+            self.shares.insert(justification.dealer, justification.value);
+        }
+    }
+
+    // function to combine the shares of every qualified dealer into this trustee's key share
+    pub fn finish(&self, qualified: &BTreeSet<u64>) -> Result<Trustee, VotingError> {
+        let mut share = Scalar::ZERO;
+        for dealer in qualified {
+            share += self.shares.get(dealer)
+                .ok_or_else(|| VotingError::SetupFailed(format!("trustee {} has no share from trustee {}", self.index, dealer)))?;
+        }
+        Ok(Trustee::new(self.index, share))
+    }
+
+}
+
+// function to decide which dealers stay qualified once complaints have been answered
+//
+// a dealer is disqualified for malformed commitments or for any complaint that is not
+// answered with a share matching those commitments
+pub fn qualified(
+    commitments: &[Commitments],
+    complaints: &[Complaint],
+    justifications: &[Justification],
+    threshold: usize,
+    num_participants: usize,
+) -> BTreeSet<u64> {
+    commitments.iter()
+        .filter(|c| c.verify(threshold, num_participants))
+        .filter(|c| {
+            complaints.iter().filter(|complaint| complaint.dealer == c.dealer).all(|complaint| {
+                justifications.iter().any(|j| {
+                    j.dealer == c.dealer && j.recipient == complaint.accuser && c.verify_share(j.recipient, &j.value)
+                })
+            })
+        })
+        .map(|c| c.dealer)
+        .collect()
+}
+
+// function to derive the public election key from the qualified dealers' commitments
+pub fn election_key(
+    commitments: &[Commitments],
+    qualified: &BTreeSet<u64>,
+    threshold: usize,
+    num_participants: usize,
+) -> Result<ElectionKey, VotingError> {
+
+    // every qualified dealer needs exactly one set of commitments
+    let qualified_commitments: Vec<&Commitments> = commitments.iter()
+        .filter(|c| qualified.contains(&c.dealer))
+        .collect();
+    if qualified.is_empty() || qualified_commitments.len() != qualified.len() {
+        return Err(VotingError::SetupFailed("qualified trustees are missing commitments".to_string()));
+    }
+
+    // the joint key and each trustee's public share are sums over the qualified dealers
+    Ok(ElectionKey {
+        public_key: qualified_commitments.iter().map(|c| c.points[0]).sum(),
+        threshold,
+        verification_keys: (1..=num_participants as u64)
+            .map(|index| qualified_commitments.iter().map(|c| evaluate_commitments(&c.points, index)).sum())
+            .collect(),
+    })
+
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // function to run an honest ceremony in-process, relaying every message through bincode
+    pub(crate) fn ceremony(threshold: usize, num_participants: usize) -> (ElectionKey, Vec<Trustee>) {
+        let mut participants: Vec<Participant> = (1..=num_participants as u64)
+            .map(|i| Participant::new(i, threshold, num_participants).unwrap())
+            .collect();
+        let commitments: Vec<Commitments> = participants.iter()
+            .map(|p| bincode::deserialize(&bincode::serialize(&p.commitments()).unwrap()).unwrap())
+            .collect();
+        let shares: Vec<Share> = participants.iter().flat_map(|p| p.shares()).collect();
+        for participant in participants.iter_mut() {
+            for c in &commitments {
+                assert!(participant.receive_commitments(c));
+            }
+            for share in &shares {
+                let share: Share = bincode::deserialize(&bincode::serialize(share).unwrap()).unwrap();
+                assert!(participant.receive_share(&share).is_none());
+            }
+        }
+        let qualified = qualified(&commitments, &[], &[], threshold, num_participants);
+        let key = election_key(&commitments, &qualified, threshold, num_participants).unwrap();
+        let trustees = participants.iter().map(|p| p.finish(&qualified).unwrap()).collect();
+        (key, trustees)
+    }
+
+    #[test]
+    fn ceremony_shares_match_the_verification_keys() {
+        let (key, trustees) = ceremony(2, 3);
+        for trustee in &trustees {
+            let partial = trustee.partial_decrypt(&[Default::default()]);
+            assert!(key.verify_partial(&[Default::default()], &partial));
+        }
+    }
+
+    #[test]
+    fn answered_complaint_keeps_the_dealer_qualified() {
+        let mut participants: Vec<Participant> = (1..=3).map(|i| Participant::new(i, 2, 3).unwrap()).collect();
+        let commitments: Vec<Commitments> = participants.iter().map(|p| p.commitments()).collect();
+        let mut bad = participants[0].shares()[1].clone();
+        bad.value += Scalar::ONE;
+        participants[1].receive_commitments(&commitments[0]);
+        let complaint = participants[1].receive_share(&bad).unwrap();
+
+        let justification = participants[0].justify(&complaint).unwrap();
+        participants[1].receive_justification(&justification);
+        assert!(qualified(&commitments, std::slice::from_ref(&complaint), std::slice::from_ref(&justification), 2, 3).contains(&1));
+
+        let mut false_answer = justification;
+        false_answer.value += Scalar::ONE;
+        assert!(!qualified(&commitments, &[complaint], &[false_answer], 2, 3).contains(&1));
+    }
+
+    #[test]
+    fn commitments_with_wrong_degree_are_rejected() {
+        let mut commitments = Participant::new(1, 2, 3).unwrap().commitments();
+        commitments.points.push(RISTRETTO_BASEPOINT_POINT);
+        assert!(!commitments.verify(2, 3));
+    }
+}
@@ -0,0 +1,60 @@
+// import types from libaries and modules
+use curve25519_dalek::ristretto::CompressedRistretto;
+use merlin::Transcript;
+pub use voting_core::election::Election;
+
+// proof transcripts bound to an election
+pub trait ElectionTranscript {
+
+    // function to start a proof transcript bound to this election, its key and the ballot
+    fn transcript(&self, public_key: &CompressedRistretto, nullifier: &[u8; 32]) -> Transcript;
+
+    // function to start the transcript for the aggregated contest proof
+    fn contest_transcript(&self, public_key: &CompressedRistretto, nullifier: &[u8; 32]) -> Transcript;
+
+}
+
+// election transcript implementation
+impl ElectionTranscript for Election {
+
+    fn transcript(&self, public_key: &CompressedRistretto, nullifier: &[u8; 32]) -> Transcript {
+        let mut transcript = Transcript::new(b"VoteProof");
+        transcript.append_message(b"election-id", self.id.as_bytes());
+        transcript.append_message(b"manifest", &self.manifest_hash);
+        transcript.append_message(b"election-key", public_key.as_bytes());
+        transcript.append_message(b"nullifier", nullifier);
+        transcript
+    }
+
+    fn contest_transcript(&self, public_key: &CompressedRistretto, nullifier: &[u8; 32]) -> Transcript {
+        let mut transcript = self.transcript(public_key, nullifier);
+        transcript.append_message(b"dom-sep", b"contests");
+        transcript
+    }
+
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // function to build an always open election with three candidates and one contest
+    pub(crate) fn election(id: &str) -> Election {
+        let names = ["a", "b", "c"].iter().map(|name| name.to_string()).collect();
+        Election::new(id.to_string(), names, vec!["question".to_string()], 8).unwrap()
+    }
+
+    #[test]
+    fn transcript_is_bound_to_the_manifest() {
+        let election = election("election_1");
+        let mut renamed = election.clone();
+        renamed.manifest_hash[0] ^= 1;
+        let key = CompressedRistretto::default();
+        let challenge = |election: &Election| {
+            let mut bytes = [0u8; 32];
+            election.transcript(&key, &[0u8; 32]).challenge_bytes(b"test", &mut bytes);
+            bytes
+        };
+        assert_ne!(challenge(&election), challenge(&renamed));
+    }
+}
@@ -0,0 +1,102 @@
+// import types from libaries and modules
+use std::collections::HashMap;
+use std::iter::Sum;
+use std::ops::AddAssign;
+use bulletproofs::PedersenGens;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use serde::{Deserialize, Serialize};
+
+// exponential elgamal ciphertext (r * B, m * B + r * PK)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ciphertext {
+    pub c1: RistrettoPoint, // ephemeral key
+    pub c2: RistrettoPoint, // message masked by the election key
+}
+
+// function to build the encryption generators for an election key
+//
+// c2 has the shape of a pedersen commitment with the election key as blinding generator,
+// but whoever knows the secret key can open it to any message, so the range and constraint
+// system proofs commit under independent generators and an equality proof links them to c2
+pub fn election_gens(public_key: RistrettoPoint) -> PedersenGens {
+    PedersenGens {
+        B: RISTRETTO_BASEPOINT_POINT,
+        B_blinding: public_key,
+    }
+}
+
+// ciphertext implementation
+impl Ciphertext {
+
+    // function to encrypt a value under the election generators
+    pub fn encrypt(pc_gens: &PedersenGens, value: Scalar, randomness: Scalar) -> Self {
+        Self {
+            c1: randomness * pc_gens.B,
+            c2: pc_gens.commit(value, randomness),
+        }
+    }
+
+    // function to return both components in compressed form
+    pub fn compress(&self) -> (CompressedRistretto, CompressedRistretto) {
+        (self.c1.compress(), self.c2.compress())
+    }
+
+}
+
+// empty ciphertext, the encryption of zero with no randomness
+impl Default for Ciphertext {
+    fn default() -> Self {
+        Self {
+            c1: RistrettoPoint::identity(),
+            c2: RistrettoPoint::identity(),
+        }
+    }
+}
+
+// combine ciphertexts so they encrypt the sum of their messages
+impl AddAssign for Ciphertext {
+    fn add_assign(&mut self, other: Self) {
+        self.c1 += other.c1;
+        self.c2 += other.c2;
+    }
+}
+
+// add up a list of ciphertexts
+impl Sum for Ciphertext {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |mut total, ciphertext| {
+            total += ciphertext;
+            total
+        })
+    }
+}
+
+// function to recover a small m from m * B with baby-step giant-step
+pub fn discrete_log(point: &RistrettoPoint, max: u64) -> Option<u64> {
+
+    // baby steps j * B for j in [0, m)
+    let step = ((max as f64).sqrt() as u64).saturating_add(1);
+    let mut baby_steps = HashMap::with_capacity(step as usize);
+    let mut current = RistrettoPoint::identity();
+    for j in 0..step {
+        baby_steps.insert(current.compress(), j);
+        current += RISTRETTO_BASEPOINT_POINT;
+    }
+
+    // giant steps subtract m * B until a baby step matches
+    let giant_step = -(Scalar::from(step) * RISTRETTO_BASEPOINT_POINT);
+    let mut current = *point;
+    for i in 0..=max / step {
+        if let Some(j) = baby_steps.get(&current.compress()) {
+            let value = i * step + j;
+            return (value <= max).then_some(value);
+        }
+        current += giant_step;
+    }
+
+    None
+
+}
@@ -1,79 +1,277 @@
 // import modules
+mod backend;
+mod ballot_proof;
+mod contest_proof;
+mod dkg;
+mod election;
+mod elgamal;
+mod membership_proof;
+mod mixnet;
+mod threshold;
 mod voter_registration;
 mod vote_proof;
 mod vote_submission;
 
 // import types from imported modules
-use voter_registration::{Voter, Party};
-use vote_proof::Vote;
+use backend::ProofKind;
+use dkg::{Commitments, Participant};
+use election::Election;
+use mixnet::Shuffle;
+use voter_registration::{Voter, VoterNullifier};
 use vote_submission::VoteSubmission;
+use voting_core::backend::ProofBackend;
+use voting_core::ballot::Ballot;
+use voting_core::error::VotingError;
+
+// function to report an error the simulation cannot continue from and exit with its code
+fn fail(context: &str, error: VotingError) -> ! {
+    println!("{}: {}", context, error);
+    std::process::exit(error.code());
+}
 
 // election simulation
 fn main() {
-    
+
+    // `bulletproofs metrics [manifest]` only reports the cost of one ballot, for the comparison run by the snarks binary
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let metrics_only = args.first().is_some_and(|arg| arg == "metrics");
+    if metrics_only {
+        args.remove(0);
+    }
+
+    // load the election from its manifest, or from the path given on the command line
+    let path = args.first().cloned()
+        .unwrap_or_else(|| concat!(env!("CARGO_MANIFEST_DIR"), "/../elections/federal_election.toml").to_string());
+    let election = Election::load(&path)
+        .unwrap_or_else(|error| fail(&format!("Could not load {}", path), error.into()));
+    if metrics_only {
+        return metrics(election);
+    }
+    println!("Loaded {} ({}) with {} candidates and {} contests.", election.title, election.id, election.num_candidates(), election.num_contests());
+
+    // referendum answers given by every voter, one per contest in the manifest
+    let answers = (0..election.num_contests()).map(|i| (i % 2) as u64).collect::<Vec<_>>();
+
+    println!("\n--- Trustee Key Generation ---");
+
+    // five trustees jointly generate the election key, any three of whom can decrypt
+    let (threshold, num_trustees) = (3, 5);
+    let mut participants: Vec<Participant> = (1..=num_trustees as u64)
+        .map(|index| Participant::new(index, threshold, num_trustees).unwrap_or_else(|error| fail("Trustee setup failed", error)))
+        .collect();
+
+    // round one: broadcast polynomial commitments and send each trustee a private share
+    let commitments: Vec<Commitments> = participants.iter().map(|p| p.commitments()).collect();
+    let mut shares: Vec<_> = participants.iter().flat_map(|p| p.shares()).collect();
+
+    // trustee 4 sends trustee 2 a share that does not match their commitments
+    if let Some(share) = shares.iter_mut().find(|s| s.dealer == 4 && s.recipient == 2) {
+        share.value += share.value;
+    }
+
+    // round two: every trustee checks the commitments and their shares, complaining about bad ones
+    let mut complaints = Vec::new();
+    for participant in participants.iter_mut() {
+        for c in &commitments {
+            participant.receive_commitments(c);
+        }
+        for share in &shares {
+            if let Some(complaint) = participant.receive_share(share) {
+                println!("Trustee {} complains about the share from trustee {}.", complaint.accuser, complaint.dealer);
+                complaints.push(complaint);
+            }
+        }
+    }
+
+    // round three: accused trustees publicly reveal the disputed share, trustee 4 reveals a wrong one
+    let mut justifications: Vec<_> = complaints.iter()
+        .filter_map(|complaint| participants[complaint.dealer as usize - 1].justify(complaint))
+        .collect();
+    for justification in justifications.iter_mut().filter(|j| j.dealer == 4) {
+        justification.value += justification.value;
+    }
+    for participant in participants.iter_mut() {
+        for justification in &justifications {
+            participant.receive_justification(justification);
+        }
+    }
+
+    // the election key and key shares come from the dealers that stayed qualified
+    let qualified = dkg::qualified(&commitments, &complaints, &justifications, threshold, num_trustees);
+    println!("Qualified dealers: {:?}", qualified);
+    let election_key = dkg::election_key(&commitments, &qualified, threshold, num_trustees)
+        .unwrap_or_else(|error| fail("Election key generation failed", error));
+    let trustees: Vec<_> = participants.iter()
+        .map(|p| p.finish(&qualified).unwrap_or_else(|error| fail("Trustee key share failed", error)))
+        .collect();
+
     // initialize voting system
-    let mut submission = VoteSubmission::new();
-    
+    let parties = election.candidates.clone();
+    let mut submission = VoteSubmission::new(election, election_key)
+        .unwrap_or_else(|error| fail("Ballot box setup failed", error));
+
     println!("\n--- Testing Votes for Each Party ---");
 
     // create one vote for each party
-    for (i, party) in [
-        Party::Liberal,
-        Party::Conservative, 
-        Party::NDP,
-        Party::Green
-    ].iter().enumerate() {
-        
+    for (i, party) in parties.iter().enumerate() {
+
         // create a new voter
         let voter = Voter::new(format!("voter_{}", i));
-        println!("\nSubmitting vote for {:?}...", party);
-        
+        println!("\nSubmitting vote for {}...", party);
+
         // create and submit the vote
-        if let Some(vote) = Vote::new(*party, voter.nullifier(), &submission.bp_gens, &submission.pc_gens) {
-            if submission.submit_vote(vote) {
-                println!("Vote for {:?} accepted.", party);
-            } else {
-                println!("Vote for {:?} rejected.", party);
-            }
-        } else {
-            println!("Vote for {:?} failed.", party);
+        match submission.cast(&voter, Ballot::new(i, &answers), ProofKind::OneHot).and_then(|vote| submission.submit_vote(vote)) {
+            Ok(()) => println!("Vote for {} accepted.", party),
+            Err(error) => println!("Vote for {} rejected: {}.", party, error),
         }
 
     }
 
+    println!("\n\n--- Testing Set Membership Votes ---");
+
+    // create a voter proving their choice with the constraint system, wrapping around short candidate lists
+    let membership_voter = Voter::new("membership_voter".to_string());
+    let candidate = 2 % parties.len();
+    println!("\nSubmitting set membership vote for {}...", parties[candidate]);
+    match submission.cast(&membership_voter, Ballot::new(candidate, &answers), ProofKind::Membership).and_then(|vote| submission.submit_vote(vote)) {
+        Ok(()) => println!("Vote for {} accepted.", parties[candidate]),
+        Err(error) => println!("Vote for {} rejected: {}.", parties[candidate], error),
+    }
+
     println!("\n\n--- Testing Double Voting Error Guarding ---");
 
-    // create a new voter that will try to vote twice
+    // create a new voter that will try to vote twice, for the first candidate and then the next one
     let double_voter = Voter::new("double_voter".to_string());
-    let nullifier = double_voter.nullifier();
-    
+    let (first, second) = (0, 1 % parties.len());
+
     // duplicate voter testers first vote
-    println!("\nFirst Vote: Voting for Liberal...");
-    if let Some(vote) = Vote::new(Party::Liberal, nullifier, &submission.bp_gens, &submission.pc_gens) {
-        if submission.submit_vote(vote) {
-            println!("Vote accepted.");
-        } else {
-            println!("Vote rejected.");
+    println!("\nFirst Vote: Voting for {}...", parties[first]);
+    match submission.cast(&double_voter, Ballot::new(first, &answers), ProofKind::OneHot).and_then(|vote| submission.submit_vote(vote)) {
+        Ok(()) => println!("Vote accepted."),
+        Err(error) => println!("Vote rejected: {}.", error),
+    }
+
+    // duplicate voter testers second vote, re-created from their exported key
+    let recreated_voter = Voter::from_key("double_voter".to_string(), double_voter.export_key());
+    println!("\nSecond Vote (Duplicate Voter {} re-created from exported key): Voting for {}...", recreated_voter.id, parties[second]);
+    match submission.cast(&recreated_voter, Ballot::new(second, &answers), ProofKind::OneHot).and_then(|vote| submission.submit_vote(vote)) {
+        Ok(()) => println!("Vote accepted."),
+        Err(VotingError::DuplicateNullifier) => println!("Vote rejected, duplicate voter identified."),
+        Err(error) => println!("Vote rejected: {}.", error),
+    }
+
+    println!("\n\n--- Testing Batch Submission ---");
+
+    // create a batch of fresh voters
+    let mut batch = Vec::new();
+    for (i, party) in parties.iter().enumerate() {
+        let voter = Voter::new(format!("batch_voter_{}", i));
+        match submission.cast(&voter, Ballot::new(i, &answers), ProofKind::OneHot) {
+            Ok(vote) => batch.push(vote),
+            Err(error) => println!("Batch vote for {} failed: {}.", party, error),
         }
     }
 
-    // duplicate voter testers second vote
-    println!("\nSecond Vote (Duplicate Voter): Voting for Conservative...");
-    if let Some(vote) = Vote::new(Party::Conservative, nullifier, &submission.bp_gens, &submission.pc_gens) {
-        if submission.submit_vote(vote) {
-            println!("Vote accepted.");
+    // move one proof onto a different nullifier so the batch has to find it
+    if let Some(vote) = batch.last_mut() {
+        vote.nullifier = Voter::new("replayed_voter".to_string()).nullifier(&submission.election().id);
+    }
+
+    // submit the batch and report each ballot
+    println!("\nSubmitting a batch of {} votes...", batch.len());
+    for (i, result) in submission.submit_batch(batch).into_iter().enumerate() {
+        match result {
+            Ok(()) => println!("Batch vote {} accepted.", i),
+            Err(VotingError::InvalidProof) => println!("Batch vote {} rejected, invalid ballot identified.", i),
+            Err(error) => println!("Batch vote {} rejected: {}.", i, error),
+        }
+    }
+
+    println!("\n\n--- Mixing Ballots ---");
+
+    // three mixers shuffle the board in turn, the second one swaps in a ballot of its own
+    for mixer in 1..=3 {
+        let mut shuffle = match Shuffle::new(&submission.ballot_box.params.pc_gens, &submission.mix_input()) {
+            Ok(shuffle) => shuffle,
+            Err(error) => {
+                println!("Shuffle from mixer {} failed: {}.", mixer, error);
+                continue;
+            }
+        };
+        if mixer == 2 && let Ok(vote) = submission.cast(&Voter::new("injected_voter".to_string()), Ballot::new(0, &answers), ProofKind::OneHot) {
+            shuffle.ballots[0][..vote.ciphertexts.len()].copy_from_slice(&vote.ciphertexts);
+        }
+        match submission.submit_shuffle(shuffle) {
+            Ok(()) => println!("Shuffle from mixer {} verified and posted.", mixer),
+            Err(error) => println!("Shuffle from mixer {} rejected: {}.", mixer, error),
+        }
+    }
+
+    println!("\n\n--- Threshold Decryption ---");
+
+    // trustees 1, 3 and 5 decrypt the homomorphic totals, trustee 2 submits a bad share
+    let aggregate = submission.aggregate_ciphertexts();
+    let mut partials: Vec<_> = [0, 2, 4].iter().map(|&i| trustees[i].partial_decrypt(&aggregate)).collect();
+    let mut cheating = trustees[1].partial_decrypt(&aggregate);
+    cheating.shares.swap(0, 1);
+    partials.insert(0, cheating);
+    for partial in &partials {
+        if submission.election_key.verify_partial(&aggregate, partial) {
+            println!("Partial decryption from trustee {} verified.", partial.index);
         } else {
-            println!("Vote rejected, duplicate voter identified.");
+            println!("Partial decryption from trustee {} rejected, invalid proof.", partial.index);
         }
     }
 
-    // display election results
+    // display election results from the decrypted totals only
     println!("\n\n--- Election Results ---");
-    for (party, count) in submission.tally() {
-        println!("- {:?}: {}", party, count);
+    match submission.tally(&partials) {
+        Ok(tally) => tally.display(submission.election()),
+        Err(error) => println!("The tally could not be decrypted: {}.", error),
+    }
+
+    // decrypt each mixed ballot, no longer linkable to the voter who cast it
+    println!("\n\n--- Mixed Ballots ---");
+    let mixed = submission.mix_input().concat();
+    let ballot_partials: Vec<_> = [0, 2, 4].iter().map(|&i| trustees[i].partial_decrypt(&mixed)).collect();
+    match submission.decrypt_ballots(&ballot_partials) {
+        Ok(ballots) => {
+            for (i, ballot) in ballots.iter().enumerate() {
+                let (candidates, contests) = ballot.split_at(submission.election().num_candidates());
+                let candidate = candidates.iter().position(|&bit| bit == 1);
+                println!("- Ballot {}: {:?}, answers {:?}", i, candidate.map(|c| &parties[c]), contests);
+            }
+        }
+        Err(error) => println!("Mixed ballots could not be decrypted: {}.", error),
     }
 
     // display performance results
     submission.display_metrics();
-    
-}
\ No newline at end of file
+
+}
+
+// function to prove and verify one ballot of each kind, printing one line per proof
+fn metrics(election: Election) {
+
+    // a single trustee is enough to encrypt under
+    let participant = Participant::new(1, 1, 1).unwrap_or_else(|error| fail("Trustee setup failed", error));
+    let commitments = [participant.commitments()];
+    let qualified = dkg::qualified(&commitments, &[], &[], 1, 1);
+    let election_key = dkg::election_key(&commitments, &qualified, 1, 1)
+        .unwrap_or_else(|error| fail("Election key generation failed", error));
+    let answers = vec![0; election.num_contests()];
+    let submission = VoteSubmission::new(election, election_key)
+        .unwrap_or_else(|error| fail("Ballot box setup failed", error));
+
+    for (name, kind) in [("one-hot", ProofKind::OneHot), ("membership", ProofKind::Membership)] {
+        let voter = Voter::new(format!("{}_voter", name));
+        let mut vote = submission.cast(&voter, Ballot::new(0, &answers), kind)
+            .unwrap_or_else(|error| fail("Proving a ballot failed", error));
+        vote.verify(&submission.ballot_box.params, submission.election())
+            .unwrap_or_else(|error| fail("Verifying a ballot failed", error));
+        let metrics = vote.metrics();
+        println!("Bulletproofs ({}): {} bytes, proved in {:.2} ms, verified in {:.2} ms",
+            name, metrics.proof_size, metrics.gen_time as f64 / 1_000_000.0, metrics.verify_time as f64 / 1_000_000.0);
+    }
+
+}
@@ -0,0 +1,162 @@
+// import types from libaries and modules
+//
+// the r1cs api behind the yoloproofs feature of bulletproofs 5 does not build against
+// curve25519-dalek 4, so the constraint system comes from bulletproofs 4 (built on
+// curve25519-dalek-ng) and points and scalars cross between the two as canonical bytes
+use bulletproofs::PedersenGens;
+use bulletproofs_r1cs::BulletproofGens;
+use bulletproofs_r1cs::r1cs::{ConstraintSystem, LinearCombination, Prover, R1CSError, R1CSProof, Variable, Verifier};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek_ng::ristretto::CompressedRistretto as NgCompressedRistretto;
+use curve25519_dalek_ng::scalar::Scalar as NgScalar;
+use merlin::Transcript;
+
+// function to size the generators for a given number of candidates
+pub fn gens_capacity(num_candidates: usize) -> usize {
+    // one multiplier per bit, and n - 1 more for the product over the candidate set
+    (2 * num_candidates).saturating_sub(1).next_power_of_two()
+}
+
+// function to carry a scalar over to the constraint system types
+fn to_ng_scalar(scalar: &Scalar) -> NgScalar {
+    NgScalar::from_bytes_mod_order(scalar.to_bytes())
+}
+
+// function to carry the commitment generators over to the constraint system types
+fn to_ng_gens(pc_gens: &PedersenGens) -> Result<bulletproofs_r1cs::PedersenGens, R1CSError> {
+    let b = NgCompressedRistretto(pc_gens.B.compress().to_bytes()).decompress();
+    let b_blinding = NgCompressedRistretto(pc_gens.B_blinding.compress().to_bytes()).decompress();
+    match (b, b_blinding) {
+        (Some(b), Some(b_blinding)) => Ok(bulletproofs_r1cs::PedersenGens { B: b, B_blinding: b_blinding }),
+        _ => Err(R1CSError::FormatError),
+    }
+}
+
+// function to commit to the vote v = sum(i * b_i) under the candidate-weighted sum of the bit commitments,
+// so the vote variable opens to the index the ballot's bits encode without a separate commitment being sent
+fn vote_commitment(commitments: &[CompressedRistretto]) -> Result<NgCompressedRistretto, R1CSError> {
+    let mut vote = RistrettoPoint::default();
+    for (i, commitment) in commitments.iter().enumerate() {
+        vote += commitment.decompress().ok_or(R1CSError::FormatError)? * Scalar::from(i as u64);
+    }
+    Ok(NgCompressedRistretto(vote.compress().to_bytes()))
+}
+
+// function to enforce that the committed bits are one-hot and that the vote they encode is a member of the candidate set
+fn membership_gadget<CS: ConstraintSystem>(cs: &mut CS, bits: &[Variable], vote: Variable) -> Result<(), R1CSError> {
+
+    // each committed value must be a bit: b * (b - 1) = 0
+    for &bit in bits {
+        let (_, _, product) = cs.multiply(bit.into(), bit - NgScalar::one());
+        cs.constrain(product.into());
+    }
+
+    // the bits must sum to exactly one
+    let total: LinearCombination = bits.iter().map(|&bit| (bit, NgScalar::one())).collect();
+    cs.constrain(total - NgScalar::one());
+
+    candidate_gadget(cs, vote, bits.len())
+
+}
+
+// function to enforce (v - 0)(v - 1)...(v - (n - 1)) = 0, so the vote is one of the n candidates
+fn candidate_gadget<CS: ConstraintSystem>(cs: &mut CS, vote: Variable, num_candidates: usize) -> Result<(), R1CSError> {
+
+    // each multiplier takes the product so far and the next factor
+    let mut product: LinearCombination = vote.into();
+    for i in 1..num_candidates {
+        let (_, _, next) = cs.multiply(product, vote - NgScalar::from(i as u64));
+        product = next.into();
+    }
+    cs.constrain(product);
+
+    Ok(())
+
+}
+
+// function to prove the committed bits encode a valid candidate
+pub fn prove(
+    transcript: &mut Transcript,
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    values: &[Scalar],
+    blindings: &[Scalar],
+) -> Result<(R1CSProof, Vec<CompressedRistretto>), R1CSError> {
+
+    // commit to every candidate bit
+    let ng_gens = to_ng_gens(pc_gens)?;
+    let mut prover = Prover::new(&ng_gens, transcript);
+    let (commitments, bits): (Vec<CompressedRistretto>, Vec<Variable>) = values.iter().zip(blindings)
+        .map(|(v, r)| {
+            let (commitment, bit) = prover.commit(to_ng_scalar(v), to_ng_scalar(r));
+            (CompressedRistretto(commitment.to_bytes()), bit)
+        })
+        .unzip();
+
+    // commit to the vote the bits encode, blinded by the same weighted sum of the bit blindings
+    let weights = (0..values.len() as u64).map(Scalar::from);
+    let vote: Scalar = values.iter().zip(weights.clone()).map(|(v, i)| v * i).sum();
+    let vote_blinding: Scalar = blindings.iter().zip(weights).map(|(r, i)| r * i).sum();
+    let (_, vote) = prover.commit(to_ng_scalar(&vote), to_ng_scalar(&vote_blinding));
+
+    // build and prove the constraint system
+    membership_gadget(&mut prover, &bits, vote)?;
+    let proof = prover.prove(bp_gens)?;
+    Ok((proof, commitments))
+
+}
+
+// function to verify the committed bits encode a valid candidate
+pub fn verify(
+    proof: &R1CSProof,
+    transcript: &mut Transcript,
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    commitments: &[CompressedRistretto],
+) -> Result<(), R1CSError> {
+
+    // bind every candidate commitment
+    let ng_gens = to_ng_gens(pc_gens)?;
+    let mut verifier = Verifier::new(transcript);
+    let bits: Vec<Variable> = commitments.iter()
+        .map(|c| verifier.commit(NgCompressedRistretto(c.to_bytes())))
+        .collect();
+
+    // the vote commitment is derived from the bit commitments rather than taken from the prover
+    let vote = verifier.commit(vote_commitment(commitments)?);
+
+    // rebuild and check the constraint system
+    membership_gadget(&mut verifier, &bits, vote)?;
+    verifier.verify(proof, &ng_gens, bp_gens)
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // function to prove and verify the candidate check alone on a committed vote
+    fn check_candidate(vote: u64, num_candidates: usize) -> Result<(), R1CSError> {
+        let pc_gens = to_ng_gens(&PedersenGens::default())?;
+        let bp_gens = BulletproofGens::new(gens_capacity(num_candidates), 1);
+        let mut transcript = Transcript::new(b"candidate-test");
+        let mut prover = Prover::new(&pc_gens, &mut transcript);
+        let (commitment, variable) = prover.commit(NgScalar::from(vote), NgScalar::from(7u64));
+        candidate_gadget(&mut prover, variable, num_candidates)?;
+        let proof = prover.prove(&bp_gens)?;
+
+        let mut transcript = Transcript::new(b"candidate-test");
+        let mut verifier = Verifier::new(&mut transcript);
+        let variable = verifier.commit(commitment);
+        candidate_gadget(&mut verifier, variable, num_candidates)?;
+        verifier.verify(&proof, &pc_gens, &bp_gens)
+    }
+
+    #[test]
+    fn vote_outside_the_candidate_set_fails_the_product() {
+        assert!(check_candidate(0, 4).is_ok());
+        assert!(check_candidate(3, 4).is_ok());
+        assert!(check_candidate(4, 4).is_err());
+    }
+}
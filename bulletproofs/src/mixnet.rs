@@ -0,0 +1,243 @@
+// import types from libaries and modules
+//
+// re-encryption mix with a sako-kilian cut-and-choose proof: the mixer publishes shadow
+// shuffles of its input, and for each one the challenge bit decides whether it opens the
+// link from the input to the shadow or from the shadow to the output, never both
+use std::time::Instant;
+use bulletproofs::PedersenGens;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use voting_core::error::VotingError;
+use crate::ballot_proof::TranscriptProtocol;
+use crate::elgamal::Ciphertext;
+
+// number of shadow shuffles, a cheating mixer passes with probability 2^-SHUFFLE_ROUNDS
+pub const SHUFFLE_ROUNDS: usize = 128;
+
+// permutation and re-encryption randomness linking two ballot lists
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShuffleOpening {
+    pub permutation: Vec<usize>, // output ballot i is input ballot permutation[i]
+    pub randomness: Vec<Vec<Scalar>>, // re-encryption randomness per output ciphertext
+}
+
+// cut-and-choose proof that the output is a re-encrypted permutation of the input
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShuffleProof {
+    pub shadows: Vec<Vec<Vec<Ciphertext>>>, // independent shuffles of the input
+    pub openings: Vec<ShuffleOpening>, // opening of each shadow towards the input or the output
+}
+
+// shuffle structure
+pub struct Shuffle {
+    pub ballots: Vec<Vec<Ciphertext>>, // re-encrypted ballots in shuffled order
+    pub proof: ShuffleProof, // proof of a correct shuffle
+    pub proof_size: usize, // proof size
+    pub gen_time: u128, // shuffle and proof generation time
+    pub verify_time: u128, // proof verification time
+}
+
+// function to permute and re-encrypt a list of ballots
+fn reencrypt(pc_gens: &PedersenGens, input: &[Vec<Ciphertext>]) -> (Vec<Vec<Ciphertext>>, ShuffleOpening) {
+    let mut rng = OsRng;
+
+    // random permutation of the ballots
+    let mut permutation: Vec<usize> = (0..input.len()).collect();
+    permutation.shuffle(&mut rng);
+
+    // add a fresh encryption of zero to every ciphertext
+    let randomness: Vec<Vec<Scalar>> = permutation.iter()
+        .map(|&i| input[i].iter().map(|_| Scalar::random(&mut rng)).collect())
+        .collect();
+    let output = permutation.iter().zip(&randomness)
+        .map(|(&i, row)| apply(pc_gens, &input[i], row))
+        .collect();
+
+    (output, ShuffleOpening { permutation, randomness })
+
+}
+
+// function to re-encrypt one ballot with the given randomness
+fn apply(pc_gens: &PedersenGens, ballot: &[Ciphertext], randomness: &[Scalar]) -> Vec<Ciphertext> {
+    ballot.iter().zip(randomness).map(|(ciphertext, r)| {
+        let mut ciphertext = *ciphertext;
+        ciphertext += Ciphertext::encrypt(pc_gens, Scalar::ZERO, *r);
+        ciphertext
+    }).collect()
+}
+
+// function to check an opening maps the input list onto the output list
+fn check_opening(pc_gens: &PedersenGens, input: &[Vec<Ciphertext>], output: &[Vec<Ciphertext>], opening: &ShuffleOpening) -> bool {
+
+    // the opening must be a permutation of every ballot
+    if opening.permutation.len() != input.len() || output.len() != input.len() || opening.randomness.len() != input.len() {
+        return false;
+    }
+    let mut seen = vec![false; input.len()];
+    for &i in &opening.permutation {
+        if i >= input.len() || std::mem::replace(&mut seen[i], true) {
+            return false;
+        }
+    }
+
+    // every output ballot must re-encrypt its input ballot
+    output.iter().zip(&opening.permutation).zip(&opening.randomness).all(|((ballot, &i), row)| {
+        row.len() == input[i].len() && *ballot == apply(pc_gens, &input[i], row)
+    })
+
+}
+
+// function to derive one challenge bit per shadow shuffle
+fn challenge_bits(
+    pc_gens: &PedersenGens,
+    input: &[Vec<Ciphertext>],
+    output: &[Vec<Ciphertext>],
+    shadows: &[Vec<Vec<Ciphertext>>],
+) -> Vec<bool> {
+
+    // bind the election key and every list on the bulletin board
+    let mut transcript = Transcript::new(b"ShuffleProof");
+    transcript.append_point(b"election-key", &pc_gens.B_blinding.compress());
+    for (label, list) in [(b"input" as &'static [u8], input), (b"output", output)] {
+        transcript.append_u64(label, list.len() as u64);
+        bind_ballots(&mut transcript, list);
+    }
+    for shadow in shadows {
+        bind_ballots(&mut transcript, shadow);
+    }
+
+    let mut bytes = vec![0u8; shadows.len().div_ceil(8)];
+    transcript.challenge_bytes(b"shuffle-challenge", &mut bytes);
+    (0..shadows.len()).map(|i| bytes[i / 8] >> (i % 8) & 1 == 1).collect()
+
+}
+
+// function to append a ballot list to the transcript
+fn bind_ballots(transcript: &mut Transcript, ballots: &[Vec<Ciphertext>]) {
+    for ciphertext in ballots.iter().flatten() {
+        let (c1, c2) = ciphertext.compress();
+        transcript.append_point(b"ciphertext-c1", &c1);
+        transcript.append_point(b"ciphertext-c2", &c2);
+    }
+}
+
+// shuffle implementation
+impl Shuffle {
+
+    // function to shuffle a ballot list and prove it was done correctly
+    pub fn new(pc_gens: &PedersenGens, input: &[Vec<Ciphertext>]) -> Result<Self, VotingError> {
+
+        // shuffle generation
+        let start_time = Instant::now();
+        let (ballots, opening) = reencrypt(pc_gens, input);
+
+        // shadow shuffles of the input, committed before the challenge
+        let (shadows, shadow_openings): (Vec<_>, Vec<_>) = (0..SHUFFLE_ROUNDS)
+            .map(|_| reencrypt(pc_gens, input))
+            .unzip();
+        let bits = challenge_bits(pc_gens, input, &ballots, &shadows);
+
+        // open each shadow towards the input, or compose it with the real shuffle towards the output
+        let openings = shadow_openings.into_iter().zip(bits).map(|(shadow, bit)| {
+            if !bit {
+                return shadow;
+            }
+            let mut position = vec![0; input.len()];
+            for (k, &i) in shadow.permutation.iter().enumerate() {
+                position[i] = k;
+            }
+            let (permutation, randomness) = opening.permutation.iter().zip(&opening.randomness)
+                .map(|(&i, row)| {
+                    let k = position[i];
+                    let diff = row.iter().zip(&shadow.randomness[k]).map(|(r, t)| r - t).collect();
+                    (k, diff)
+                })
+                .unzip();
+            ShuffleOpening { permutation, randomness }
+        }).collect();
+        let proof = ShuffleProof { shadows, openings };
+        let gen_time = start_time.elapsed().as_nanos();
+
+        // proof serialization
+        let proof_size = bincode::serialized_size(&proof)
+            .map_err(|error| VotingError::Serialization(error.to_string()))? as usize;
+
+        Ok(Self {
+            ballots,
+            proof,
+            proof_size,
+            gen_time,
+            verify_time: 0,
+        })
+
+    }
+
+    // function to verify the shuffle against the ballot list it claims to mix
+    pub fn verify(&mut self, pc_gens: &PedersenGens, input: &[Vec<Ciphertext>]) -> bool {
+
+        // verification time initialization
+        let start_time = Instant::now();
+
+        // every shadow must be opened according to its challenge bit
+        let proof = &self.proof;
+        let result = proof.shadows.len() == SHUFFLE_ROUNDS
+            && proof.openings.len() == SHUFFLE_ROUNDS
+            && challenge_bits(pc_gens, input, &self.ballots, &proof.shadows).into_iter()
+                .zip(proof.shadows.iter().zip(&proof.openings))
+                .all(|(bit, (shadow, opening))| {
+                    if bit {
+                        check_opening(pc_gens, shadow, &self.ballots, opening)
+                    } else {
+                        check_opening(pc_gens, input, shadow, opening)
+                    }
+                });
+
+        // measure verification time
+        self.verify_time = start_time.elapsed().as_nanos();
+        result
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    // function to encrypt a few two-entry ballots
+    fn ballots(pc_gens: &PedersenGens) -> Vec<Vec<Ciphertext>> {
+        (0..4u64).map(|i| vec![
+            Ciphertext::encrypt(pc_gens, Scalar::from(i), Scalar::random(&mut OsRng)),
+            Ciphertext::encrypt(pc_gens, Scalar::from(i * 2), Scalar::random(&mut OsRng)),
+        ]).collect()
+    }
+
+    #[test]
+    fn honest_shuffle_verifies() {
+        let pc_gens = PedersenGens::default();
+        let input = ballots(&pc_gens);
+        let mut shuffle = Shuffle::new(&pc_gens, &input).unwrap();
+        assert!(shuffle.verify(&pc_gens, &input));
+    }
+
+    #[test]
+    fn shuffle_with_replaced_ballot_is_rejected() {
+        let pc_gens = PedersenGens::default();
+        let input = ballots(&pc_gens);
+        let mut shuffle = Shuffle::new(&pc_gens, &input).unwrap();
+        shuffle.ballots[0][0].c2 += RISTRETTO_BASEPOINT_POINT;
+        assert!(!shuffle.verify(&pc_gens, &input));
+    }
+
+    #[test]
+    fn shuffle_of_other_input_is_rejected() {
+        let pc_gens = PedersenGens::default();
+        let input = ballots(&pc_gens);
+        let mut shuffle = Shuffle::new(&pc_gens, &input).unwrap();
+        assert!(!shuffle.verify(&pc_gens, &ballots(&pc_gens)));
+    }
+}
@@ -0,0 +1,167 @@
+// import types from libaries and modules
+use std::collections::HashSet;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use serde::{Deserialize, Serialize};
+use voting_core::error::VotingError;
+use crate::ballot_proof::{DleqProof, TranscriptProtocol};
+use crate::elgamal::Ciphertext;
+
+// public election key with everything needed to check trustee decryptions
+#[derive(Clone, Debug)]
+pub struct ElectionKey {
+    pub public_key: RistrettoPoint, // joint encryption key
+    pub threshold: usize, // number of trustees needed to decrypt
+    pub verification_keys: Vec<RistrettoPoint>, // public share of trustee i at position i - 1
+}
+
+// trustee holding one share of the election secret key
+#[derive(Clone, Debug)]
+pub struct Trustee {
+    pub index: u64, // position on the sharing polynomial, starting at one
+    share: Scalar, // secret key share
+}
+
+// one trustee's decryption shares with proofs they were computed correctly
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartialDecryption {
+    pub index: u64, // trustee that produced the shares
+    pub shares: Vec<RistrettoPoint>, // share * c1 for each ciphertext
+    pub proofs: Vec<DleqProof>, // proof each share uses the trustee's key share
+}
+
+// function to compute the lagrange coefficient of a trustee for interpolation at zero
+fn lagrange_at_zero(index: u64, indices: &[u64]) -> Scalar {
+    let x = Scalar::from(index);
+    let (numerator, denominator) = indices.iter()
+        .filter(|&&other| other != index)
+        .fold((Scalar::ONE, Scalar::ONE), |(num, den), &other| {
+            let other = Scalar::from(other);
+            (num * other, den * (other - x))
+        });
+    numerator * denominator.invert()
+}
+
+// function to start the transcript of one decryption share
+fn share_transcript(index: u64, ciphertext: &Ciphertext) -> Transcript {
+    let mut transcript = Transcript::new(b"PartialDecryption");
+    transcript.append_u64(b"trustee", index);
+    transcript.append_point(b"c1", &ciphertext.c1.compress());
+    transcript
+}
+
+// trustee implementation
+impl Trustee {
+
+    // function to create a trustee from their key share
+    pub fn new(index: u64, share: Scalar) -> Self {
+        Self { index, share }
+    }
+
+    // function to compute this trustee's decryption shares
+    pub fn partial_decrypt(&self, ciphertexts: &[Ciphertext]) -> PartialDecryption {
+        let (shares, proofs) = ciphertexts.iter().map(|ciphertext| {
+            let mut transcript = share_transcript(self.index, ciphertext);
+            let proof = DleqProof::prove(&mut transcript, &RISTRETTO_BASEPOINT_POINT, &ciphertext.c1, &self.share);
+            (self.share * ciphertext.c1, proof)
+        }).unzip();
+        PartialDecryption { index: self.index, shares, proofs }
+    }
+
+}
+
+// election key implementation
+impl ElectionKey {
+
+    // function to check a trustee's decryption shares against their verification key
+    pub fn verify_partial(&self, ciphertexts: &[Ciphertext], partial: &PartialDecryption) -> bool {
+
+        // the trustee must exist and cover every ciphertext
+        let Some(verification_key) = (partial.index as usize).checked_sub(1).and_then(|i| self.verification_keys.get(i)) else {
+            return false;
+        };
+        if partial.shares.len() != ciphertexts.len() || partial.proofs.len() != ciphertexts.len() {
+            return false;
+        }
+
+        // each share must use the same secret as the verification key
+        ciphertexts.iter().zip(&partial.shares).zip(&partial.proofs).all(|((ciphertext, share), proof)| {
+            let mut transcript = share_transcript(partial.index, ciphertext);
+            proof.verify(&mut transcript, (&RISTRETTO_BASEPOINT_POINT, verification_key), (&ciphertext.c1, share))
+        })
+
+    }
+
+    // function to combine threshold many valid partial decryptions into m * B per ciphertext
+    pub fn decrypt(&self, ciphertexts: &[Ciphertext], partials: &[PartialDecryption]) -> Result<Vec<RistrettoPoint>, VotingError> {
+
+        // keep the first valid partial from each distinct trustee
+        let mut seen = HashSet::new();
+        let valid: Vec<&PartialDecryption> = partials.iter()
+            .filter(|partial| self.verify_partial(ciphertexts, partial) && seen.insert(partial.index))
+            .take(self.threshold)
+            .collect();
+        if valid.len() < self.threshold {
+            return Err(VotingError::InsufficientDecryptions);
+        }
+
+        // interpolate secret * c1 at zero and strip it from c2
+        let indices: Vec<u64> = valid.iter().map(|partial| partial.index).collect();
+        let coefficients: Vec<Scalar> = indices.iter().map(|&index| lagrange_at_zero(index, &indices)).collect();
+        Ok(ciphertexts.iter().enumerate().map(|(j, ciphertext)| {
+            let mask: RistrettoPoint = valid.iter().zip(&coefficients)
+                .map(|(partial, coefficient)| coefficient * partial.shares[j])
+                .sum();
+            ciphertext.c2 - mask
+        }).collect())
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use crate::dkg::tests::ceremony;
+    use crate::elgamal::{self, discrete_log};
+
+    // function to encrypt small values under a dealt key
+    fn encrypt(key: &ElectionKey, values: &[u64]) -> Vec<Ciphertext> {
+        let pc_gens = elgamal::election_gens(key.public_key);
+        values.iter()
+            .map(|&value| Ciphertext::encrypt(&pc_gens, Scalar::from(value), Scalar::random(&mut OsRng)))
+            .collect()
+    }
+
+    #[test]
+    fn any_threshold_of_trustees_decrypts() {
+        let (key, trustees) = ceremony(3, 5);
+        let ciphertexts = encrypt(&key, &[0, 7, 42]);
+        let partials: Vec<_> = [4, 0, 2].iter().map(|&i| trustees[i].partial_decrypt(&ciphertexts)).collect();
+        let decrypted = key.decrypt(&ciphertexts, &partials).unwrap();
+        let values: Vec<_> = decrypted.iter().map(|point| discrete_log(point, 100).unwrap()).collect();
+        assert_eq!(values, vec![0, 7, 42]);
+    }
+
+    #[test]
+    fn fewer_than_threshold_trustees_cannot_decrypt() {
+        let (key, trustees) = ceremony(3, 5);
+        let ciphertexts = encrypt(&key, &[1]);
+        let partials: Vec<_> = trustees[..2].iter().map(|t| t.partial_decrypt(&ciphertexts)).collect();
+        assert_eq!(key.decrypt(&ciphertexts, &partials), Err(VotingError::InsufficientDecryptions));
+    }
+
+    #[test]
+    fn tampered_partial_decryption_is_rejected() {
+        let (key, trustees) = ceremony(2, 3);
+        let ciphertexts = encrypt(&key, &[1]);
+        let mut partial = trustees[0].partial_decrypt(&ciphertexts);
+        partial.shares[0] += RISTRETTO_BASEPOINT_POINT;
+        assert!(!key.verify_partial(&ciphertexts, &partial));
+        let honest = trustees[1].partial_decrypt(&ciphertexts);
+        assert_eq!(key.decrypt(&ciphertexts, &[partial, honest]), Err(VotingError::InsufficientDecryptions));
+    }
+}
@@ -1,67 +1,95 @@
 // import types from libaries and modules
 use std::time::Instant;
-use bincode;
-use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
-use curve25519_dalek::ristretto::CompressedRistretto;
+use bulletproofs::RangeProof;
+use bulletproofs_r1cs::r1cs::R1CSProof;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use merlin::Transcript;
 use rand::rngs::OsRng;
-use crate::voter_registration::Party;
+use serde::Serialize;
+use voting_core::error::VotingError;
+use crate::backend::BulletproofParams;
+use crate::ballot_proof::{BatchVerifier, BitProof, DleqProof, EqualityProof, TranscriptProtocol};
+use crate::contest_proof;
+use crate::election::{Election, ElectionTranscript};
+use crate::elgamal::Ciphertext;
+use crate::membership_proof;
 
 // vote structure
 pub struct Vote {
-    pub party: Party, // voted party
     pub nullifier: [u8; 32], // identifier
-    pub proof: RangeProof, // range proof
-    pub commitment: CompressedRistretto, // blinding factor commitment
+    pub ciphertexts: Vec<Ciphertext>, // encrypted one-hot bit per candidate
+    pub proof: BallotProof, // proof the ciphertexts encrypt a valid candidate
+    pub contest_ciphertexts: Vec<Ciphertext>, // encrypted contest answers, zero padded
+    pub contest_proof: Option<ContestProof>, // proof the contest answers are in range
     pub proof_size: usize, // proof size
     pub gen_time: u128, // proof generation time
     pub verify_time: u128, // proof verification time
 }
 
+// ballot proof variants
+pub enum BallotProof {
+    OneHot {
+        bit_proofs: Vec<BitProof>, // proof each ciphertext holds a bit
+        sum_proof: DleqProof, // proof the bits sum to one
+    },
+    Membership {
+        proof: Box<R1CSProof>, // constraint system proof that the bits are one-hot and the vote v they encode has prod(v - i) = 0
+        commitments: Vec<CompressedRistretto>, // independent pedersen commitment to each bit
+        equality_proofs: Vec<EqualityProof>, // proof each ciphertext holds its committed bit
+    },
+}
+
+// proofs over the contest answers
+#[derive(Serialize)]
+pub struct ContestProof {
+    pub range_proof: RangeProof, // aggregated range proof over the commitments
+    pub commitments: Vec<CompressedRistretto>, // independent pedersen commitment to each padded answer
+    pub equality_proofs: Vec<EqualityProof>, // proof each ciphertext holds its committed answer
+}
+
 // implement vote
 impl Vote {
-    
+
     // function to create a new vote
     pub fn new(
-        party: Party,
+        candidate: usize,
+        answers: &[u64],
         nullifier: [u8; 32],
-        bp_gens: &BulletproofGens,
-        pc_gens: &PedersenGens,
-    ) -> Option<Self> {
-        let mut rng = OsRng;
-        
-        // only allow the four valid parties
-        if party.as_u64() > 3 {
-            return None;
-        }
+        election: &Election,
+        params: &BulletproofParams,
+    ) -> Result<Self, VotingError> {
 
         // variable initialization
-        let vote_value = party.as_u64();
-        let blinding = Scalar::random(&mut rng);
-        let mut transcript = Transcript::new(b"VoteProof");
+        let pc_gens = &params.pc_gens;
+        let (bits, values, randomness) = Self::one_hot(candidate, election.num_candidates())?;
+        let mut transcript = election.transcript(&pc_gens.B_blinding.compress(), &nullifier);
 
         // proof generation
         let start_time = Instant::now();
-        let (proof, commitment) = RangeProof::prove_single(
-            bp_gens,
-            pc_gens,
-            &mut transcript,
-            vote_value,
-            &blinding,
-            8,
-        ).ok()?;
+        let ciphertexts: Vec<Ciphertext> = values.iter().zip(&randomness)
+            .map(|(v, r)| Ciphertext::encrypt(pc_gens, *v, *r))
+            .collect();
+        bind_ciphertexts(&mut transcript, &ciphertexts);
+        let bit_proofs: Vec<BitProof> = ciphertexts.iter().zip(&bits).zip(&randomness)
+            .map(|((ciphertext, &bit), r)| BitProof::prove(&mut transcript, pc_gens, ciphertext, bit, r))
+            .collect();
+        let randomness_sum: Scalar = randomness.iter().sum();
+        let sum_proof = DleqProof::prove(&mut transcript, &pc_gens.B, &pc_gens.B_blinding, &randomness_sum);
+        let (contest_ciphertexts, contest_proof) = Self::prove_contests(answers, &nullifier, election, params)?;
         let gen_time = start_time.elapsed().as_nanos();
 
         // proof serialization
-        let proof_size = bincode::serialized_size(&proof).ok()? as usize;
+        let proof_size = bincode::serialized_size(&(&bit_proofs, &sum_proof, &contest_proof))
+            .map_err(|error| VotingError::Serialization(error.to_string()))? as usize;
 
         // constructed vote with the completed proof
-        Some(Self {
-            party,
+        Ok(Self {
             nullifier,
-            proof,
-            commitment,
+            ciphertexts,
+            proof: BallotProof::OneHot { bit_proofs, sum_proof },
+            contest_ciphertexts,
+            contest_proof,
             proof_size,
             gen_time,
             verify_time: 0,
@@ -69,27 +97,354 @@ impl Vote {
 
     }
 
-    // function for proof verification
-    pub fn verify(&mut self, bp_gens: &BulletproofGens, pc_gens: &PedersenGens) -> bool {
+    // function to create a new vote proven with the set membership constraint system
+    pub fn new_membership(
+        candidate: usize,
+        answers: &[u64],
+        nullifier: [u8; 32],
+        election: &Election,
+        params: &BulletproofParams,
+    ) -> Result<Self, VotingError> {
 
-        let mut transcript = Transcript::new(b"VoteProof");
+        // variable initialization, the commitments get blindings of their own
+        let (_, values, randomness) = Self::one_hot(candidate, election.num_candidates())?;
+        let blindings = random_scalars(values.len());
+        let public_key = params.pc_gens.B_blinding.compress();
+        let mut transcript = election.transcript(&public_key, &nullifier);
 
-        // verification time initialization
+        // proof generation, the constraint system commits to each bit under the independent generators
         let start_time = Instant::now();
+        let (proof, commitments) = membership_proof::prove(
+            &mut transcript,
+            &params.r1cs_gens,
+            &params.commit_gens,
+            &values,
+            &blindings,
+        ).map_err(|error| VotingError::ProvingFailed(error.to_string()))?;
+        let ciphertexts: Vec<Ciphertext> = values.iter().zip(&randomness)
+            .map(|(v, r)| Ciphertext::encrypt(&params.pc_gens, *v, *r))
+            .collect();
+        let mut transcript = election.transcript(&public_key, &nullifier);
+        let equality_proofs = prove_equality(&mut transcript, params, &ciphertexts, &commitments, &values, &randomness, &blindings);
+        let (contest_ciphertexts, contest_proof) = Self::prove_contests(answers, &nullifier, election, params)?;
+        let gen_time = start_time.elapsed().as_nanos();
 
-        // verify the proof
-        let result = self.proof.verify_single(
-            bp_gens, 
-            pc_gens, 
-            &mut transcript, 
-            &self.commitment, 
-            8
-        ).is_ok();
+        // proof serialization
+        let proof_size = proof.serialized_size() + bincode::serialized_size(&(&commitments, &equality_proofs, &contest_proof))
+            .map_err(|error| VotingError::Serialization(error.to_string()))? as usize;
 
-        // measure verification time
-        self.verify_time = start_time.elapsed().as_nanos();
-        result
+        // constructed vote with the completed proof
+        Ok(Self {
+            nullifier,
+            ciphertexts,
+            proof: BallotProof::Membership { proof: Box::new(proof), commitments, equality_proofs },
+            contest_ciphertexts,
+            contest_proof,
+            proof_size,
+            gen_time,
+            verify_time: 0,
+        })
 
     }
 
-}
\ No newline at end of file
+    // function to prove every contest answer with one aggregated range proof
+    fn prove_contests(
+        answers: &[u64],
+        nullifier: &[u8; 32],
+        election: &Election,
+        params: &BulletproofParams,
+    ) -> Result<(Vec<Ciphertext>, Option<ContestProof>), VotingError> {
+
+        // every contest needs an answer inside its range
+        if answers.len() != election.num_contests()
+            || answers.iter().any(|&answer| answer >> election.contest_bits != 0) {
+            return Err(VotingError::InvalidAnswers);
+        }
+
+        // ballots without contests carry no contest proof
+        if answers.is_empty() {
+            return Ok((Vec::new(), None));
+        }
+
+        // the range proof commits to each padded answer under the independent generators
+        let public_key = params.pc_gens.B_blinding.compress();
+        let mut transcript = election.contest_transcript(&public_key, nullifier);
+        let (range_proof, commitments, blindings) = contest_proof::prove(
+            &mut transcript,
+            &params.bp_gens,
+            &params.commit_gens,
+            answers,
+            election.contest_bits,
+        ).map_err(|error| VotingError::ProvingFailed(error.to_string()))?;
+
+        // encrypt each padded answer and tie it to its commitment
+        let mut values: Vec<Scalar> = answers.iter().map(|&answer| Scalar::from(answer)).collect();
+        values.resize(blindings.len(), Scalar::ZERO);
+        let randomness = random_scalars(values.len());
+        let ciphertexts: Vec<Ciphertext> = values.iter().zip(&randomness)
+            .map(|(v, r)| Ciphertext::encrypt(&params.pc_gens, *v, *r))
+            .collect();
+        let mut transcript = election.contest_transcript(&public_key, nullifier);
+        let equality_proofs = prove_equality(&mut transcript, params, &ciphertexts, &commitments, &values, &randomness, &blindings);
+
+        Ok((ciphertexts, Some(ContestProof { range_proof, commitments, equality_proofs })))
+
+    }
+
+    // function to build the one-hot encoding of a candidate
+    #[allow(clippy::type_complexity)]
+    fn one_hot(candidate: usize, num_candidates: usize) -> Result<(Vec<bool>, Vec<Scalar>, Vec<Scalar>), VotingError> {
+
+        // only allow a candidate on the ballot
+        if candidate >= num_candidates {
+            return Err(VotingError::InvalidCandidate { candidate, num_candidates });
+        }
+
+        let bits: Vec<bool> = (0..num_candidates).map(|i| i == candidate).collect();
+        let values = bits.iter().map(|&b| Scalar::from(b as u64)).collect();
+        Ok((bits, values, random_scalars(num_candidates)))
+
+    }
+
+    // function to check the aggregated contest proof and queue its equality proofs
+    fn queue_contests(
+        &self,
+        election: &Election,
+        params: &BulletproofParams,
+        batch: &mut BatchVerifier,
+    ) -> bool {
+
+        // the proof must cover exactly the padded contests of this election
+        match &self.contest_proof {
+            None => election.num_contests() == 0 && self.contest_ciphertexts.is_empty(),
+            Some(proof) => {
+                if election.num_contests() == 0
+                    || self.contest_ciphertexts.len() != contest_proof::gens_capacity(election.num_contests()) {
+                    return false;
+                }
+                let public_key = params.pc_gens.B_blinding.compress();
+                let mut transcript = election.contest_transcript(&public_key, &self.nullifier);
+                let in_range = contest_proof::verify(
+                    &proof.range_proof,
+                    &mut transcript,
+                    &params.bp_gens,
+                    &params.commit_gens,
+                    &proof.commitments,
+                    election.contest_bits,
+                ).is_ok();
+                let mut transcript = election.contest_transcript(&public_key, &self.nullifier);
+                in_range && queue_equality(&mut transcript, params, &self.contest_ciphertexts, &proof.commitments, &proof.equality_proofs, batch)
+            }
+        }
+
+    }
+
+    // function to queue the proof into a batch, false if the ballot is already known to be invalid
+    pub fn queue_verification(
+        &self,
+        election: &Election,
+        params: &BulletproofParams,
+        batch: &mut BatchVerifier,
+    ) -> bool {
+
+        // ensure the ballot covers every candidate
+        if self.ciphertexts.len() != election.num_candidates() {
+            return false;
+        }
+
+        // aggregated range proofs are checked on their own
+        if !self.queue_contests(election, params, batch) {
+            return false;
+        }
+
+        // the transcript binds the proof to this ballot's nullifier, election and key
+        let pc_gens = &params.pc_gens;
+        let public_key = pc_gens.B_blinding.compress();
+        let mut transcript = election.transcript(&public_key, &self.nullifier);
+
+        match &self.proof {
+            BallotProof::OneHot { bit_proofs, sum_proof } => {
+
+                // every candidate needs exactly one bit proof
+                if bit_proofs.len() != self.ciphertexts.len() {
+                    return false;
+                }
+                bind_ciphertexts(&mut transcript, &self.ciphertexts);
+
+                // the bits must each be zero or one and add up to one
+                let total: Ciphertext = self.ciphertexts.iter().copied().sum();
+                self.ciphertexts.iter().zip(bit_proofs).all(|(ciphertext, proof)| proof.queue(&mut transcript, pc_gens, ciphertext, batch))
+                    && sum_proof.queue(&mut transcript, (&pc_gens.B, &total.c1), (&pc_gens.B_blinding, &(total.c2 - pc_gens.B)), batch)
+
+            }
+            // the constraint system api has no batch verifier, so these are checked on their own
+            BallotProof::Membership { proof, commitments, equality_proofs } => {
+                let is_member = membership_proof::verify(proof, &mut transcript, &params.r1cs_gens, &params.commit_gens, commitments).is_ok();
+                let mut transcript = election.transcript(&public_key, &self.nullifier);
+                is_member && queue_equality(&mut transcript, params, &self.ciphertexts, commitments, equality_proofs, batch)
+            }
+        }
+
+    }
+
+}
+
+// function to draw fresh randomness for each value
+fn random_scalars(count: usize) -> Vec<Scalar> {
+    let mut rng = OsRng;
+    (0..count).map(|_| Scalar::random(&mut rng)).collect()
+}
+
+// function to append every ciphertext to the transcript
+fn bind_ciphertexts(transcript: &mut Transcript, ciphertexts: &[Ciphertext]) {
+    for ciphertext in ciphertexts {
+        let (c1, c2) = ciphertext.compress();
+        transcript.append_point(b"ciphertext-c1", &c1);
+        transcript.append_point(b"ciphertext-c2", &c2);
+    }
+}
+
+// function to append every ciphertext and its commitment to the transcript
+fn bind_commitments(transcript: &mut Transcript, ciphertexts: &[Ciphertext], commitments: &[CompressedRistretto]) {
+    transcript.append_message(b"dom-sep", b"equality");
+    bind_ciphertexts(transcript, ciphertexts);
+    for commitment in commitments {
+        transcript.append_point(b"commitment", commitment);
+    }
+}
+
+// function to prove each ciphertext holds the value behind its commitment
+fn prove_equality(
+    transcript: &mut Transcript,
+    params: &BulletproofParams,
+    ciphertexts: &[Ciphertext],
+    commitments: &[CompressedRistretto],
+    values: &[Scalar],
+    randomness: &[Scalar],
+    blindings: &[Scalar],
+) -> Vec<EqualityProof> {
+    bind_commitments(transcript, ciphertexts, commitments);
+    values.iter().zip(randomness).zip(blindings)
+        .map(|((v, r), s)| EqualityProof::prove(transcript, (&params.pc_gens, &params.commit_gens), v, r, s))
+        .collect()
+}
+
+// function to queue the equality proofs of a list of ciphertexts and their commitments
+fn queue_equality(
+    transcript: &mut Transcript,
+    params: &BulletproofParams,
+    ciphertexts: &[Ciphertext],
+    commitments: &[CompressedRistretto],
+    proofs: &[EqualityProof],
+    batch: &mut BatchVerifier,
+) -> bool {
+
+    // every ciphertext needs exactly one commitment and one equality proof
+    if proofs.len() != ciphertexts.len() || commitments.len() != ciphertexts.len() {
+        return false;
+    }
+    let Some(points) = commitments.iter().map(|c| c.decompress()).collect::<Option<Vec<RistrettoPoint>>>() else {
+        return false;
+    };
+
+    bind_commitments(transcript, ciphertexts, commitments);
+    ciphertexts.iter().zip(&points).zip(proofs)
+        .all(|((ciphertext, commitment), proof)| proof.queue(transcript, (&params.pc_gens, &params.commit_gens), ciphertext, commitment, batch))
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bulletproofs::PedersenGens;
+    use voting_core::backend::ProofBackend;
+    use crate::backend::BulletproofParams;
+    use crate::election::tests::election;
+
+    // function to build the parameters used by the tests
+    fn params() -> BulletproofParams {
+        Vote::setup(&election("election_1"), PedersenGens::default().B_blinding).unwrap()
+    }
+
+    #[test]
+    fn one_hot_vote_verifies_in_its_own_context() {
+        let params = params();
+        let election = election("election_1");
+        let mut vote = Vote::new(1, &[3], [1u8; 32], &election, &params).unwrap();
+        assert_eq!(vote.verify(&params, &election), Ok(()));
+    }
+
+    #[test]
+    fn invalid_ballots_are_reported() {
+        let params = params();
+        let election = election("election_1");
+        let vote = Vote::new(3, &[3], [1u8; 32], &election, &params);
+        assert_eq!(vote.err(), Some(VotingError::InvalidCandidate { candidate: 3, num_candidates: 3 }));
+        let vote = Vote::new(1, &[256], [1u8; 32], &election, &params);
+        assert_eq!(vote.err(), Some(VotingError::InvalidAnswers));
+    }
+
+    #[test]
+    fn one_hot_proof_replayed_under_new_nullifier_is_rejected() {
+        let params = params();
+        let election = election("election_1");
+        let mut vote = Vote::new(1, &[3], [1u8; 32], &election, &params).unwrap();
+        vote.nullifier = [2u8; 32];
+        assert_eq!(vote.verify(&params, &election), Err(VotingError::InvalidProof));
+    }
+
+    #[test]
+    fn one_hot_proof_replayed_in_other_election_is_rejected() {
+        let params = params();
+        let mut vote = Vote::new(1, &[3], [1u8; 32], &election("election_1"), &params).unwrap();
+        assert_eq!(vote.verify(&params, &election("election_2")), Err(VotingError::InvalidProof));
+    }
+
+    #[test]
+    fn membership_proof_replayed_under_new_nullifier_is_rejected() {
+        let params = params();
+        let election = election("election_1");
+        let mut vote = Vote::new_membership(2, &[3], [1u8; 32], &election, &params).unwrap();
+        assert_eq!(vote.verify(&params, &election), Ok(()));
+        vote.nullifier = [2u8; 32];
+        assert_eq!(vote.verify(&params, &election), Err(VotingError::InvalidProof));
+    }
+
+    #[test]
+    fn membership_proof_replayed_in_other_election_is_rejected() {
+        let params = params();
+        let mut vote = Vote::new_membership(2, &[3], [1u8; 32], &election("election_1"), &params).unwrap();
+        assert_eq!(vote.verify(&params, &election("election_2")), Err(VotingError::InvalidProof));
+    }
+
+    #[test]
+    fn contest_ciphertext_from_another_ballot_is_rejected() {
+        let params = params();
+        let election = election("election_1");
+        let mut vote = Vote::new(1, &[3], [1u8; 32], &election, &params).unwrap();
+        let other = Vote::new(1, &[200], [1u8; 32], &election, &params).unwrap();
+
+        // the range proof covers the commitments, so only the equality proofs tie them to the ciphertexts
+        vote.contest_ciphertexts = other.contest_ciphertexts;
+        assert_eq!(vote.verify(&params, &election), Err(VotingError::InvalidProof));
+    }
+
+    #[test]
+    fn one_hot_ciphertext_with_swapped_ephemeral_key_is_rejected() {
+        let params = params();
+        let election = election("election_1");
+        let mut vote = Vote::new(1, &[3], [1u8; 32], &election, &params).unwrap();
+        vote.ciphertexts[0].c1 += params.pc_gens.B;
+        assert_eq!(vote.verify(&params, &election), Err(VotingError::InvalidProof));
+    }
+
+    #[test]
+    fn membership_ciphertext_with_swapped_ephemeral_key_is_rejected() {
+        let params = params();
+        let election = election("election_1");
+        let mut vote = Vote::new_membership(2, &[3], [1u8; 32], &election, &params).unwrap();
+        vote.ciphertexts[2].c1 += params.pc_gens.B;
+        assert_eq!(vote.verify(&params, &election), Err(VotingError::InvalidProof));
+    }
+
+}
@@ -1,107 +1,300 @@
 // import types from libaries and modules
-use std::collections::HashSet;
-use bulletproofs::{BulletproofGens, PedersenGens};
+use voting_core::ballot::Ballot;
+use voting_core::ballot_box::BallotBox;
+use voting_core::error::VotingError;
+use voting_core::tally::Tally;
+use crate::backend::ProofKind;
+use crate::contest_proof;
+use crate::election::Election;
+use crate::elgamal::{self, Ciphertext};
+use crate::mixnet::Shuffle;
+use crate::threshold::{ElectionKey, PartialDecryption};
 use crate::vote_proof::Vote;
-use crate::voter_registration::Party;
+use crate::voter_registration::Voter;
 
 // vote submission structure
 pub struct VoteSubmission {
-    nullifiers: HashSet<[u8; 32]>, // voter identifier
-    pub bp_gens: BulletproofGens, // proof generator
-    pub pc_gens: PedersenGens, // commitment generator
-    votes: Vec<Party>, // votes
-    total_gen_time: u128, // proof generation time
-    total_verify_time: u128, // proof verification time
-    total_proof_size: usize, // size of all proofs
-    total_votes: usize, // number of votes
-    total_memory_used: usize, // memory usage
+    pub ballot_box: BallotBox<Vote>, // accepted votes, nullifiers and proof metrics
+    pub election_key: ElectionKey, // public key the trustees decrypt the tally under
+    mixed: Option<Vec<Vec<Ciphertext>>>, // ballots after the last accepted shuffle
+    total_shuffle_time: u128, // shuffle proof generation time
+    total_shuffle_verify_time: u128, // shuffle proof verification time
+    total_shuffle_size: usize, // size of all shuffle proofs
+    total_shuffles: usize, // number of accepted shuffles
 }
 
 // vote submission implementation
 impl VoteSubmission {
-    
+
     // function to create a new vote submission
-    pub fn new() -> Self {
-
-        Self {
-            nullifiers: HashSet::new(),
-            bp_gens: BulletproofGens::new(8, 1),
-            pc_gens: PedersenGens::default(),
-            votes: Vec::new(),
-            total_gen_time: 0,
-            total_verify_time: 0,
-            total_proof_size: 0,
-            total_votes: 0,
-            total_memory_used: 0,
-        }
+    pub fn new(election: Election, election_key: ElectionKey) -> Result<Self, VotingError> {
+
+        Ok(Self {
+            ballot_box: BallotBox::setup(election, election_key.public_key)?,
+            election_key,
+            mixed: None,
+            total_shuffle_time: 0,
+            total_shuffle_verify_time: 0,
+            total_shuffle_size: 0,
+            total_shuffles: 0,
+        })
+
+    }
+
+    // function to return the election the ballots are cast in
+    pub fn election(&self) -> &Election {
+        &self.ballot_box.election
+    }
 
+    // function to encrypt and prove a voter's ballot for this election
+    pub fn cast(&self, voter: &Voter, ballot: Ballot, kind: ProofKind) -> Result<Vote, VotingError> {
+        self.ballot_box.prove(voter, (ballot, kind)).map(|(vote, _)| vote)
     }
 
     // function to submit the vote
-    pub fn submit_vote(&mut self, mut vote: Vote) -> bool {
+    pub fn submit_vote(&mut self, vote: Vote) -> Result<(), VotingError> {
+        self.ballot_box.submit(vote)
+    }
 
-        // start measuring memory usage
-        let before = memory_stats::memory_stats()
-            .map(|m| m.physical_mem)
-            .unwrap_or(0);
+    // function to submit a batch of votes, returning whether each one was accepted
+    pub fn submit_batch(&mut self, votes: Vec<Vote>) -> Vec<Result<(), VotingError>> {
+        self.ballot_box.submit_batch(votes)
+    }
 
-        // ensure voter is not a duplicate voter
-        if self.nullifiers.contains(&vote.nullifier) {
-            return false;
-        }
+    // function to return each accepted ballot as its candidate followed by its unpadded contest ciphertexts
+    fn ballots(&self) -> Vec<Vec<Ciphertext>> {
+        let num_contests = self.election().num_contests();
+        self.ballot_box.votes().iter().map(|vote| {
+            let mut ciphertexts = vote.ciphertexts.clone();
+            ciphertexts.extend_from_slice(&vote.contest_ciphertexts[..num_contests]);
+            ciphertexts
+        }).collect()
+    }
 
-        // verify the proof
-        if !vote.verify(&self.bp_gens, &self.pc_gens) {
-            return false;
-        }
+    // function to return the ballot list the next mixer has to shuffle
+    pub fn mix_input(&self) -> Vec<Vec<Ciphertext>> {
+        self.mixed.clone().unwrap_or_else(|| self.ballots())
+    }
+
+    // function to check a mixer's shuffle against the bulletin board and post its output
+    pub fn submit_shuffle(&mut self, mut shuffle: Shuffle) -> Result<(), VotingError> {
 
-        self.nullifiers.insert(vote.nullifier);
-        self.votes.push(vote.party);
+        // verify the proof against the current board
+        if !shuffle.verify(&self.ballot_box.params.pc_gens, &self.mix_input()) {
+            return Err(VotingError::InvalidShuffle);
+        }
 
-        // stop measuring memory
-        let after = memory_stats::memory_stats().map(|m| m.physical_mem).unwrap_or(0);
-        self.total_memory_used += after.saturating_sub(before);
+        // no more ballots are cast once mixing has started
+        self.ballot_box.close();
 
         // update the measured performance metrics
-        self.total_votes += 1;
-        self.total_gen_time += vote.gen_time;
-        self.total_verify_time += vote.verify_time;
-        self.total_proof_size += vote.proof_size;
+        self.total_shuffles += 1;
+        self.total_shuffle_time += shuffle.gen_time;
+        self.total_shuffle_verify_time += shuffle.verify_time;
+        self.total_shuffle_size += shuffle.proof_size;
+        self.mixed = Some(shuffle.ballots);
 
-        true
+        Ok(())
 
     }
 
-    // function to count the votes for each party
-    pub fn tally(&self) -> Vec<(Party, usize)> {
-        let mut counts = vec![0; 4];
-        for party in &self.votes {
-            counts[*party as usize] += 1;
+    // function to decrypt each mixed ballot on its own from the trustees' partial decryptions
+    pub fn decrypt_ballots(&self, partials: &[PartialDecryption]) -> Result<Vec<Vec<usize>>, VotingError> {
+
+        // only mixed ballots are decrypted individually
+        let ballots = self.mixed.as_ref().ok_or(VotingError::NotMixed)?;
+        let width = self.election().num_candidates() + self.election().num_contests();
+        let ciphertexts = ballots.concat();
+        let points = self.election_key.decrypt(&ciphertexts, partials)?;
+
+        // candidate entries are bits, contest entries are bounded by the largest answer
+        let max_answer = self.max_answer();
+        points.chunks(width.max(1)).map(|ballot| {
+            ballot.iter().enumerate().map(|(i, point)| {
+                let max = if i < self.election().num_candidates() { 1 } else { max_answer };
+                elgamal::discrete_log(point, max).map(|value| value as usize).ok_or(VotingError::DecryptionFailed)
+            }).collect()
+        }).collect()
+
+    }
+
+    // function to compute the largest answer a contest ciphertext can hold
+    fn max_answer(&self) -> u64 {
+        (1 << self.election().contest_bits) - 1
+    }
+
+    // function to homomorphically combine the accepted ciphertexts per candidate and contest
+    pub fn aggregate_ciphertexts(&self) -> Vec<Ciphertext> {
+        let mut aggregate = vec![Ciphertext::default(); self.election().num_candidates() + self.election().num_contests()];
+        for ballot in &self.ballots() {
+            for (total, ciphertext) in aggregate.iter_mut().zip(ballot) {
+                *total += *ciphertext;
+            }
         }
-        Party::values().iter().map(|&p| (p, counts[p as usize])).collect()
+        aggregate
+    }
+
+    // function to count the votes for each candidate and total each contest from the trustees' partial decryptions
+    pub fn tally(&self, partials: &[PartialDecryption]) -> Result<Tally, VotingError> {
+        let mut candidates = self.decrypt_aggregate(partials)?;
+        let contests = candidates.split_off(self.election().num_candidates());
+        Ok(Tally { candidates, contests })
+    }
+
+    // function to decrypt the aggregate ciphertexts and recover each small total
+    fn decrypt_aggregate(&self, partials: &[PartialDecryption]) -> Result<Vec<usize>, VotingError> {
+
+        // combine threshold many verified partial decryptions
+        let aggregate = self.aggregate_ciphertexts();
+        let points = self.election_key.decrypt(&aggregate, partials)?;
+
+        // candidate counts are bounded by the number of ballots, contest totals by the largest answer
+        let votes = self.ballot_box.votes().len() as u64;
+        let max_answer = self.max_answer();
+        points.iter().enumerate().map(|(i, point)| {
+            let max = if i < self.election().num_candidates() { votes } else { votes.saturating_mul(max_answer) };
+            elgamal::discrete_log(point, max).map(|value| value as usize).ok_or(VotingError::DecryptionFailed)
+        }).collect()
+
     }
 
     // function to display the performance metrics
     pub fn display_metrics(&self) {
 
-        println!("\n\n--- Performance Metrics ---");
-
-        // zero vote error guard
-        if self.total_votes == 0 {
-            println!("No valid votes were submitted.");
+        self.ballot_box.display_metrics();
+        if self.ballot_box.metrics.total_votes == 0 {
             return;
         }
 
-        println!("Number of voted processed: {}", self.total_votes);
-        println!("Average proof generation time: {:.2} ms", 
-            self.total_gen_time as f64 / self.total_votes as f64 / 1_000_000.0);
-        println!("Average verification time: {:.2} ms", 
-            self.total_verify_time as f64 / self.total_votes as f64 / 1_000_000.0);
-        println!("Average proof size: {} bytes", 
-            self.total_proof_size / self.total_votes);
-        println!("Total memory usage: {} KB", 
-            self.total_memory_used / 1024);
+        if self.total_shuffles > 0 {
+            println!("Number of shuffles verified: {}", self.total_shuffles);
+            println!("Average shuffle generation time: {:.2} ms",
+                self.total_shuffle_time as f64 / self.total_shuffles as f64 / 1_000_000.0);
+            println!("Average shuffle verification time: {:.2} ms",
+                self.total_shuffle_verify_time as f64 / self.total_shuffles as f64 / 1_000_000.0);
+            println!("Average shuffle proof size: {} bytes",
+                self.total_shuffle_size / self.total_shuffles);
+        }
+
+        // aggregated range proofs grow logarithmically with the number of contests
+        println!("Contest proof size by number of contests ({} bit answers):", self.election().contest_bits);
+        for num_contests in [1, 2, 4, 8, 16] {
+            if let Some(size) = contest_proof::proof_size(num_contests, self.election().contest_bits) {
+                println!("- {} contests: {} bytes", num_contests, size);
+            }
+        }
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use voting_core::backend::ProofBackend;
+    use crate::election::tests::election;
+    use crate::dkg::tests::ceremony;
+    use crate::threshold::Trustee;
+    use crate::voter_registration::VoterNullifier;
+
+    // function to build the submission used by the tests with its trustees
+    fn setup() -> (VoteSubmission, Vec<Trustee>) {
+        let (key, trustees) = ceremony(2, 3);
+        let election = election("election_1");
+        (VoteSubmission::new(election, key).unwrap(), trustees)
+    }
+
+    // function to build the submission used by the tests
+    fn submission() -> VoteSubmission {
+        setup().0
+    }
+
+    // function to cast a one-hot ballot for a voter
+    fn vote(submission: &VoteSubmission, voter: &Voter, candidate: usize) -> Vote {
+        submission.cast(voter, Ballot::new(candidate, &[1]), ProofKind::OneHot).unwrap()
+    }
+
+    #[test]
+    fn nullifier_is_deterministic_per_key_and_election() {
+        let voter = Voter::new("voter".to_string());
+        let recreated = Voter::from_key("voter".to_string(), voter.export_key());
+        assert_eq!(voter.nullifier("election_1"), recreated.nullifier("election_1"));
+        assert_ne!(voter.nullifier("election_1"), voter.nullifier("election_2"));
+    }
+
+    #[test]
+    fn recreated_voter_is_caught_as_duplicate() {
+        let mut submission = submission();
+        let voter = Voter::new("voter".to_string());
+        let first = vote(&submission, &voter, 0);
+        assert_eq!(submission.submit_vote(first), Ok(()));
 
+        let recreated = Voter::from_key("voter".to_string(), voter.export_key());
+        let second = vote(&submission, &recreated, 1);
+        assert_eq!(submission.submit_vote(second), Err(VotingError::DuplicateNullifier));
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn recreated_voter_is_caught_inside_a_batch() {
+        let mut submission = submission();
+        let voter = Voter::new("voter".to_string());
+        let recreated = Voter::from_key("voter".to_string(), voter.export_key());
+        let batch = vec![vote(&submission, &voter, 0), vote(&submission, &recreated, 2)];
+        assert_eq!(submission.submit_batch(batch), vec![Ok(()), Err(VotingError::DuplicateNullifier)]);
+    }
+
+    #[test]
+    fn forged_ballot_is_found_inside_a_batch() {
+        let mut submission = submission();
+        let mut batch: Vec<Vote> = (0..3).map(|i| vote(&submission, &Voter::new(format!("voter_{}", i)), i)).collect();
+
+        // the middle ballot's ephemeral key is swapped, so the randomized batch check fails
+        batch[1].ciphertexts[0].c1 += submission.ballot_box.params.pc_gens.B;
+        let votes: Vec<&Vote> = batch.iter().collect();
+        assert!(!Vote::batch_verify(&submission.ballot_box.params, submission.election(), &votes));
+
+        // the fallback checks each ballot and flags only the forged one
+        assert_eq!(submission.submit_batch(batch), vec![Ok(()), Err(VotingError::InvalidProof), Ok(())]);
+    }
+
+    #[test]
+    fn threshold_tally_counts_accepted_ballots() {
+        let (mut submission, trustees) = setup();
+        let ballots = [(0, 2), (2, 0), (2, 5)];
+        for (i, (candidate, answer)) in ballots.iter().enumerate() {
+            let ballot = Ballot::new(*candidate, &[*answer]);
+            let vote = submission.cast(&Voter::new(format!("voter_{}", i)), ballot, ProofKind::OneHot).unwrap();
+            assert_eq!(submission.submit_vote(vote), Ok(()));
+        }
+
+        let aggregate = submission.aggregate_ciphertexts();
+        let partials: Vec<_> = trustees[1..].iter().map(|t| t.partial_decrypt(&aggregate)).collect();
+        assert_eq!(submission.tally(&partials), Ok(Tally { candidates: vec![1, 0, 2], contests: vec![7] }));
+        assert_eq!(submission.tally(&partials[..1]), Err(VotingError::InsufficientDecryptions));
+    }
+
+    #[test]
+    fn mixed_ballots_decrypt_individually() {
+        let (mut submission, trustees) = setup();
+        for (i, candidate) in [0, 2].iter().enumerate() {
+            let ballot = Ballot::new(*candidate, &[3]);
+            let vote = submission.cast(&Voter::new(format!("voter_{}", i)), ballot, ProofKind::Membership).unwrap();
+            assert_eq!(submission.submit_vote(vote), Ok(()));
+        }
+
+        // a shuffle of a stale board is rejected, an honest one is posted
+        let pc_gens = submission.ballot_box.params.pc_gens;
+        let stale = Shuffle::new(&pc_gens, &submission.mix_input()[..1]).unwrap();
+        assert_eq!(submission.submit_shuffle(stale), Err(VotingError::InvalidShuffle));
+        let shuffle = Shuffle::new(&pc_gens, &submission.mix_input()).unwrap();
+        assert_eq!(submission.submit_shuffle(shuffle), Ok(()));
+        let late = submission.cast(&Voter::new("late_voter".to_string()), Ballot::new(1, &[3]), ProofKind::OneHot).unwrap();
+        assert_eq!(submission.submit_vote(late), Err(VotingError::ElectionClosed));
+
+        let ciphertexts = submission.mix_input().concat();
+        let partials: Vec<_> = trustees[..2].iter().map(|t| t.partial_decrypt(&ciphertexts)).collect();
+        let mut ballots = submission.decrypt_ballots(&partials).unwrap();
+        ballots.sort();
+        assert_eq!(ballots, vec![vec![0, 0, 1, 3], vec![1, 0, 0, 3]]);
+    }
+}
@@ -1,61 +1,25 @@
 // import types from libaries and modules
-use curve25519_dalek::scalar::Scalar;
-use rand::rngs::OsRng;
 use sha3::{Sha3_256, Digest};
+pub use voting_core::voter::Voter;
 
-// voter structure
-#[derive(Debug, Clone)]
-pub struct Voter {
-    pub id: String, // voter ID
-    id_blinding: Scalar, // blinding factor
-}
-
-// voter implementation
-impl Voter {
-    
-    // function to create a new voter
-    pub fn new(id: String) -> Self {
+// nullifiers of a voter in this backend
+pub trait VoterNullifier {
 
-        let mut rng = OsRng;
+    // function to compute a voters unique identifier for one election
+    fn nullifier(&self, election_id: &str) -> [u8; 32];
 
-        // constructed voter
-        Self {
-            id,
-            id_blinding: Scalar::random(&mut rng),
-        }
+}
 
-    }
+// voter nullifier implementation
+impl VoterNullifier for Voter {
 
-    // function to compute a voters unique identifier
-    pub fn nullifier(&self) -> [u8; 32] {
+    fn nullifier(&self, election_id: &str) -> [u8; 32] {
         let mut hash = Sha3_256::new();
-        hash.update(self.id.as_bytes());
-        hash.update(self.id_blinding.as_bytes());
+        hash.update(b"nullifier");
+        hash.update(self.derive_secret(b"ristretto255"));
+        hash.update((election_id.len() as u64).to_le_bytes());
+        hash.update(election_id.as_bytes());
         hash.finalize().into()
     }
 
 }
-
-// party enum
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum Party {
-    Liberal = 0,
-    Conservative = 1,
-    NDP = 2,
-    Green = 3,
-}
-
-// party implementation
-impl Party {
-
-    // function to return all the parties
-    pub fn values() -> Vec<Self> {
-        vec![Self::Liberal, Self::Conservative, Self::NDP, Self::Green]
-    }
-
-    // function to convert the enum party to u64
-    pub fn as_u64(&self) -> u64 {
-        *self as u64
-    }
-
-}
\ No newline at end of file
@@ -0,0 +1,361 @@
+// import types from libaries and modules
+use ark_ec::{pairing::{Pairing, PairingOutput}, AffineRepr, CurveGroup};
+use ark_ff::{PrimeField, Zero};
+use ark_groth16::{Proof, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use sha3::{Digest, Sha3_256};
+use voting_core::error::VotingError;
+use crate::plonk::{divide_by_linear, evaluate, msm};
+use crate::powers_of_tau::{powers, scale_each, PowersOfTau};
+
+// element of the pairing target group
+type Gt<E> = PairingOutput<E>;
+
+// powers of two secrets in both groups, taken from two independent powers-of-tau transcripts
+pub struct AggregationKey<E: Pairing> {
+    pub g_a: Vec<E::G1Affine>, // g^(a^i) for i < 2n
+    pub g_b: Vec<E::G1Affine>, // g^(b^i) for i < 2n
+    pub h_a: Vec<E::G2Affine>, // h^(a^i) for i < 2n
+    pub h_b: Vec<E::G2Affine>, // h^(b^i) for i < 2n
+}
+
+// cross terms sent in one halving round of the inner pairing product argument
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Round<E: Pairing> {
+    pub com_ab_left: (Gt<E>, Gt<E>), // commitment to the right half of A with the left half of B
+    pub com_ab_right: (Gt<E>, Gt<E>), // commitment to the left half of A with the right half of B
+    pub com_c_left: (Gt<E>, Gt<E>), // commitment to the right half of C under the left half of the key
+    pub com_c_right: (Gt<E>, Gt<E>), // commitment to the left half of C under the right half of the key
+    pub z_ab_left: Gt<E>, // pairing product of the right half of A with the left half of B
+    pub z_ab_right: Gt<E>, // pairing product of the left half of A with the right half of B
+    pub z_c_left: E::G1Affine, // right half of C weighted by the left half of the powers of r
+    pub z_c_right: E::G1Affine, // left half of C weighted by the right half of the powers of r
+}
+
+// one proof for a whole list of Groth16 proofs, logarithmic in their number
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregateProof<E: Pairing> {
+    pub com_ab: (Gt<E>, Gt<E>), // commitment to the A and B elements of every proof
+    pub com_c: (Gt<E>, Gt<E>), // commitment to the C elements of every proof
+    pub z_ab: Gt<E>, // product of e(A_i, B_i)^(r^i)
+    pub z_c: E::G1Affine, // sum of C_i * r^i
+    pub rounds: Vec<Round<E>>, // halving rounds
+    pub a: E::G1Affine, // A folded down to one element
+    pub b: E::G2Affine, // B folded down to one element
+    pub c: E::G1Affine, // C folded down to one element
+    pub v: (E::G2Affine, E::G2Affine), // folded commitment key for A and C
+    pub w: (E::G1Affine, E::G1Affine), // folded commitment key for B
+    pub v_opening: (E::G1Affine, E::G1Affine), // KZG openings showing v was folded from the key
+    pub w_opening: (E::G2Affine, E::G2Affine), // KZG openings showing w was folded from the key
+}
+
+// aggregation key implementation
+impl<E: Pairing> AggregationKey<E> {
+
+    // function to return how many powers of tau a key for up to max_proofs proofs needs
+    pub fn transcript_size(max_proofs: usize) -> usize {
+        2 * max_proofs.max(1).next_power_of_two()
+    }
+
+    // function to take the powers of a from one verified powers-of-tau transcript and those of b from another, independent one
+    pub fn from_powers_of_tau(first: &PowersOfTau<E>, second: &PowersOfTau<E>, max_proofs: usize) -> Result<Self, VotingError> {
+        first.verify()?;
+        second.verify()?;
+        let count = Self::transcript_size(max_proofs);
+        if first.size() < count || second.size() < count {
+            return Err(VotingError::SetupFailed(format!("aggregating {} proofs needs {} powers of tau", max_proofs, count)));
+        }
+
+        // with a = b the commitments to A and to C would share a key, so the transcripts must not share tau
+        if first.tau_g1[1] == second.tau_g1[1] {
+            return Err(VotingError::SetupFailed("both transcripts have the same tau".to_string()));
+        }
+        Ok(Self {
+            g_a: first.tau_g1[..count].to_vec(),
+            g_b: second.tau_g1[..count].to_vec(),
+            h_a: first.tau_g2[..count].to_vec(),
+            h_b: second.tau_g2[..count].to_vec(),
+        })
+    }
+
+    // function to return the largest number of proofs the key can aggregate
+    pub fn max_proofs(&self) -> usize {
+        self.g_a.len() / 2
+    }
+
+}
+
+// function to aggregate proofs with their public inputs, padding the list to a power of two
+pub fn aggregate<E: Pairing>(key: &AggregationKey<E>, vk: &VerifyingKey<E>, proofs: &[Proof<E>], inputs: &[Vec<E::ScalarField>]) -> Result<AggregateProof<E>, VotingError> {
+
+    if proofs.is_empty() || proofs.len() != inputs.len() || proofs.len() > key.max_proofs() {
+        return Err(VotingError::ProvingFailed(format!("cannot aggregate {} proofs with a key for {}", proofs.len(), key.max_proofs())));
+    }
+    let n = proofs.len().next_power_of_two();
+    let mut proofs = proofs.to_vec();
+    proofs.resize(n, proofs[proofs.len() - 1].clone());
+    let inputs = pad(inputs, n);
+
+    // commitment keys: powers of a and b in G2 for A and C, shifted powers in G1 for B
+    let (mut v1, mut v2) = (key.h_a[..n].to_vec(), key.h_b[..n].to_vec());
+    let (mut w1, mut w2) = (key.g_a[n..2 * n].to_vec(), key.g_b[n..2 * n].to_vec());
+    let mut a: Vec<E::G1Affine> = proofs.iter().map(|proof| proof.a).collect();
+    let mut b: Vec<E::G2Affine> = proofs.iter().map(|proof| proof.b).collect();
+    let mut c: Vec<E::G1Affine> = proofs.iter().map(|proof| proof.c).collect();
+    let com_ab = (commit(&v1, &w1, &a, &b), commit(&v2, &w2, &a, &b));
+    let com_c = (commit(&v1, &[], &c, &[]), commit(&v2, &[], &c, &[]));
+
+    // weight proof i by r^i, rescaling B and its key so the commitment is unchanged
+    let r = challenge(seed(key, vk)?, &(com_ab, com_c, inputs.as_slice()))?;
+    let mut weights = powers(r, n);
+    let inverse_weights = powers(inverse(r), n);
+    b = scale_each(&b, &weights);
+    w1 = scale_each(&w1, &inverse_weights);
+    w2 = scale_each(&w2, &inverse_weights);
+    let z_ab = E::multi_pairing(a.iter().copied(), b.iter().copied());
+    let z_c = msm(&c, &weights);
+
+    // halve every vector, sending the cross terms and folding with a challenge bound to the claimed products
+    let mut transcript = challenge(r, &(z_ab, z_c))?;
+    let mut rounds = Vec::new();
+    let mut challenges = Vec::new();
+    while a.len() > 1 {
+        let m = a.len() / 2;
+        let (a_l, a_r) = a.split_at(m);
+        let (b_l, b_r) = b.split_at(m);
+        let (c_l, c_r) = c.split_at(m);
+        let (r_l, r_r) = weights.split_at(m);
+        let (v1_l, v1_r) = v1.split_at(m);
+        let (v2_l, v2_r) = v2.split_at(m);
+        let (w1_l, w1_r) = w1.split_at(m);
+        let (w2_l, w2_r) = w2.split_at(m);
+        let round = Round {
+            com_ab_left: (commit(v1_l, w1_r, a_r, b_l), commit(v2_l, w2_r, a_r, b_l)),
+            com_ab_right: (commit(v1_r, w1_l, a_l, b_r), commit(v2_r, w2_l, a_l, b_r)),
+            com_c_left: (commit(v1_l, &[], c_r, &[]), commit(v2_l, &[], c_r, &[])),
+            com_c_right: (commit(v1_r, &[], c_l, &[]), commit(v2_r, &[], c_l, &[])),
+            z_ab_left: E::multi_pairing(a_r.iter().copied(), b_l.iter().copied()),
+            z_ab_right: E::multi_pairing(a_l.iter().copied(), b_r.iter().copied()),
+            z_c_left: msm(c_r, r_l),
+            z_c_right: msm(c_l, r_r),
+        };
+        let x = challenge(transcript, &round)?;
+        let x_inverse = inverse(x);
+        a = fold(a_l, a_r, x);
+        b = fold(b_l, b_r, x_inverse);
+        c = fold(c_l, c_r, x);
+        weights = r_l.iter().zip(r_r).map(|(left, right)| *left + x_inverse * right).collect();
+        v1 = fold(v1_l, v1_r, x_inverse);
+        v2 = fold(v2_l, v2_r, x_inverse);
+        w1 = fold(w1_l, w1_r, x);
+        w2 = fold(w2_l, w2_r, x);
+        transcript = x;
+        challenges.push(x);
+        rounds.push(round);
+    }
+
+    // prove the folded keys are the key polynomials evaluated at the secrets
+    let (v, w) = ((v1[0], v2[0]), (w1[0], w2[0]));
+    let z = challenge(transcript, &(v, w))?;
+    let v_quotient = divide_by_linear(&key_polynomial(&challenges, true), z);
+    let w_quotient = divide_by_linear(&shifted_key_polynomial(&challenges, r, n), z);
+
+    Ok(AggregateProof {
+        com_ab, com_c, z_ab, z_c, rounds,
+        a: a[0],
+        b: b[0],
+        c: c[0],
+        v,
+        w,
+        v_opening: (msm(&key.g_a[..v_quotient.len()], &v_quotient), msm(&key.g_b[..v_quotient.len()], &v_quotient)),
+        w_opening: (msm(&key.h_a[..w_quotient.len()], &w_quotient), msm(&key.h_b[..w_quotient.len()], &w_quotient)),
+    })
+
+}
+
+// function to check an aggregate proof against the public inputs of every aggregated proof
+pub fn verify<E: Pairing>(key: &AggregationKey<E>, vk: &VerifyingKey<E>, inputs: &[Vec<E::ScalarField>], proof: &AggregateProof<E>) -> Result<(), VotingError> {
+
+    let n = inputs.len().next_power_of_two();
+    if inputs.is_empty() || n > key.max_proofs() || proof.rounds.len() != n.trailing_zeros() as usize
+        || inputs.iter().any(|input| input.len() + 1 != vk.gamma_abc_g1.len()) {
+        return Err(VotingError::InvalidAggregate);
+    }
+    let inputs = pad(inputs, n);
+
+    // replay the transcript, folding the commitments and products with each challenge
+    let r = challenge(seed(key, vk)?, &(proof.com_ab, proof.com_c, inputs.as_slice()))?;
+    let (mut com_ab, mut com_c, mut z_ab, mut z_c) = (proof.com_ab, proof.com_c, proof.z_ab, proof.z_c.into_group());
+    let mut transcript = challenge(r, &(proof.z_ab, proof.z_c))?;
+    let mut challenges = Vec::new();
+    for round in &proof.rounds {
+        let x = challenge(transcript, round)?;
+        let x_inverse = inverse(x);
+        com_ab = (com_ab.0 + round.com_ab_left.0 * x + round.com_ab_right.0 * x_inverse, com_ab.1 + round.com_ab_left.1 * x + round.com_ab_right.1 * x_inverse);
+        com_c = (com_c.0 + round.com_c_left.0 * x + round.com_c_right.0 * x_inverse, com_c.1 + round.com_c_left.1 * x + round.com_c_right.1 * x_inverse);
+        z_ab = z_ab + round.z_ab_left * x + round.z_ab_right * x_inverse;
+        z_c += round.z_c_left * x + round.z_c_right * x_inverse;
+        transcript = x;
+        challenges.push(x);
+    }
+
+    // the folded elements must open the folded commitments and products
+    let (a, b, c) = ([proof.a], [proof.b], [proof.c]);
+    let folded = com_ab == (commit(&[proof.v.0], &[proof.w.0], &a, &b), commit(&[proof.v.1], &[proof.w.1], &a, &b))
+        && com_c == (commit(&[proof.v.0], &[], &c, &[]), commit(&[proof.v.1], &[], &c, &[]))
+        && z_ab == E::pairing(proof.a, proof.b)
+        && z_c == proof.c * evaluate(&key_polynomial(&challenges, true), r);
+
+    // the folded keys must be the key polynomials evaluated at the secrets
+    let z = challenge(transcript, &(proof.v, proof.w))?;
+    let (g, h) = (key.g_a[0], key.h_a[0]);
+    let v_at_z = h * evaluate(&key_polynomial(&challenges, true), z);
+    let w_at_z = g * evaluate(&shifted_key_polynomial(&challenges, r, n), z);
+    let keys = E::pairing(proof.v_opening.0, h * -z + key.h_a[1]) == E::pairing(g, -v_at_z + proof.v.0)
+        && E::pairing(proof.v_opening.1, h * -z + key.h_b[1]) == E::pairing(g, -v_at_z + proof.v.1)
+        && E::pairing(-w_at_z + proof.w.0, h) == E::pairing(g * -z + key.g_a[1], proof.w_opening.0)
+        && E::pairing(-w_at_z + proof.w.1, h) == E::pairing(g * -z + key.g_b[1], proof.w_opening.1);
+
+    // the weighted product of the Groth16 checks: e(A_i, B_i) = e(alpha, beta) e(inputs_i, gamma) e(C_i, delta)
+    let weights = powers(r, n);
+    let total: E::ScalarField = weights.iter().sum();
+    let mut weighted_inputs = vk.gamma_abc_g1[0] * total;
+    for (j, base) in vk.gamma_abc_g1[1..].iter().enumerate() {
+        let sum: E::ScalarField = inputs.iter().zip(&weights).map(|(input, weight)| input[j] * weight).sum();
+        weighted_inputs += *base * sum;
+    }
+    let groth16 = proof.z_ab == E::pairing(vk.alpha_g1, vk.beta_g2) * total
+        + E::multi_pairing([weighted_inputs.into_affine(), proof.z_c], [vk.gamma_g2, vk.delta_g2]);
+
+    if folded && keys && groth16 { Ok(()) } else { Err(VotingError::InvalidAggregate) }
+
+}
+
+// function to commit to vectors by pairing a with the G2 key v and the G1 key w with b
+fn commit<E: Pairing>(v: &[E::G2Affine], w: &[E::G1Affine], a: &[E::G1Affine], b: &[E::G2Affine]) -> Gt<E> {
+    E::multi_pairing(a.iter().chain(w).copied(), v.iter().chain(b).copied())
+}
+
+// function to start the transcript from both keys, so a proof is only checked against the keys it was made for
+fn seed<E: Pairing>(key: &AggregationKey<E>, vk: &VerifyingKey<E>) -> Result<E::ScalarField, VotingError> {
+    let vk_digest = challenge(E::ScalarField::zero(), vk)?;
+    challenge(vk_digest, &((key.g_a.as_slice(), key.g_b.as_slice()), (key.h_a.as_slice(), key.h_b.as_slice())))
+}
+
+// function to derive a non-zero challenge from the previous one and the new prover messages
+fn challenge<F: PrimeField, T: CanonicalSerialize>(previous: F, message: &T) -> Result<F, VotingError> {
+    let mut bytes = Vec::new();
+    previous.serialize_compressed(&mut bytes)
+        .and_then(|_| message.serialize_compressed(&mut bytes))
+        .map_err(|error| VotingError::Serialization(error.to_string()))?;
+    let mut counter = 0u64;
+    loop {
+        let mut hash = Sha3_256::new();
+        hash.update(b"snarkpack");
+        hash.update(counter.to_le_bytes());
+        hash.update(&bytes);
+        let challenge = F::from_le_bytes_mod_order(&hash.finalize());
+        if !challenge.is_zero() {
+            return Ok(challenge);
+        }
+        counter += 1;
+    }
+}
+
+// function to return the coefficients of prod_j (1 + y_j X^(2^j)), with y_j the challenges or their inverses
+fn key_polynomial<F: PrimeField>(challenges: &[F], inverted: bool) -> Vec<F> {
+    let mut coefficients = vec![F::one()];
+    for x in challenges.iter().rev() {
+        let y = if inverted { inverse(*x) } else { *x };
+        let high: Vec<F> = coefficients.iter().map(|coefficient| *coefficient * y).collect();
+        coefficients.extend(high);
+    }
+    coefficients
+}
+
+// function to return the coefficients of X^n f(X / r), the polynomial behind the folded G1 key
+fn shifted_key_polynomial<F: PrimeField>(challenges: &[F], r: F, n: usize) -> Vec<F> {
+    let mut coefficients = vec![F::zero(); n];
+    let scales = powers(inverse(r), n);
+    coefficients.extend(key_polynomial(challenges, false).iter().zip(scales).map(|(coefficient, scale)| *coefficient * scale));
+    coefficients
+}
+
+// function to invert a challenge, which is never zero
+fn inverse<F: PrimeField>(x: F) -> F {
+    x.inverse().unwrap_or_else(F::zero)
+}
+
+// function to compute left + x * right for each pair of points
+fn fold<A: AffineRepr>(left: &[A], right: &[A], x: A::ScalarField) -> Vec<A> {
+    let folded: Vec<A::Group> = left.iter().zip(right).map(|(l, r)| l.into_group() + *r * x).collect();
+    A::Group::normalize_batch(&folded)
+}
+
+// function to pad the public inputs to n entries by repeating the last one, matching the padded proofs
+fn pad<F: PrimeField>(inputs: &[Vec<F>], n: usize) -> Vec<Vec<F>> {
+    let mut inputs = inputs.to_vec();
+    if let Some(last) = inputs.last().cloned() {
+        inputs.resize(n, last);
+    }
+    inputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr as BlsFr, G1Affine};
+    use ark_ff::One;
+    use ark_std::rand::thread_rng;
+    use crate::election::{Election, ElectionInput};
+    use crate::vote_submission::VoteSubmission;
+    use crate::voter_registration::{RegistrationAuthority, Voter};
+
+    // function to run a powers-of-tau transcript with one contribution
+    fn transcript(size: usize) -> PowersOfTau<Bls12_381> {
+        let mut powers = PowersOfTau::new(size);
+        powers.contribute(&mut thread_rng()).unwrap();
+        powers
+    }
+
+    #[test]
+    fn aggregate_covers_every_accepted_ballot() {
+        let election = Election::new("election_1".to_string(), vec!["a".to_string(), "b".to_string()], Vec::new(), 8).unwrap();
+        let voters: Vec<Voter> = (0..3).map(|i| Voter::new(format!("voter_{}", i))).collect();
+        let mut authority = RegistrationAuthority::<BlsFr>::new();
+        let indices: Vec<usize> = voters.iter().map(|voter| authority.register(voter).unwrap()).collect();
+        let paths: Vec<_> = indices.into_iter().map(|index| authority.path(index).unwrap()).collect();
+        let submission = VoteSubmission::<Bls12_381>::new(election.clone(), authority.root()).unwrap();
+        let proofs: Vec<_> = voters.iter().zip(&paths).enumerate()
+            .map(|(i, (voter, path))| submission.cast(voter, i % 2, path).unwrap().0)
+            .collect();
+        let inputs: Vec<Vec<BlsFr>> = proofs.iter().map(|vote| vote.public_inputs(authority.root(), election.field_id()).to_vec()).collect();
+        let proofs: Vec<_> = proofs.into_iter().map(|vote| vote.proof).collect();
+
+        // the key needs two transcripts, each contributed to, with different taus
+        let first = transcript(AggregationKey::<Bls12_381>::transcript_size(4));
+        let second = transcript(AggregationKey::<Bls12_381>::transcript_size(4));
+        assert!(AggregationKey::from_powers_of_tau(&first, &first, 4).is_err());
+        assert!(AggregationKey::from_powers_of_tau(&first, &PowersOfTau::new(8), 4).is_err());
+        assert!(AggregationKey::from_powers_of_tau(&first, &second, 8).is_err());
+
+        // three proofs are padded to four and checked at once
+        let key = AggregationKey::from_powers_of_tau(&first, &second, 4).unwrap();
+        let vk = &submission.ballot_box.params.vk;
+        let aggregate = aggregate(&key, vk, &proofs, &inputs).unwrap();
+        assert_eq!(verify(&key, vk, &inputs, &aggregate), Ok(()));
+
+        // the transcript is bound to the aggregation key it was made with
+        let other_key = AggregationKey::from_powers_of_tau(&first, &transcript(8), 4).unwrap();
+        assert_eq!(verify(&other_key, vk, &inputs, &aggregate), Err(VotingError::InvalidAggregate));
+
+        // a changed public input or proof element is caught
+        let mut forged_inputs = inputs.clone();
+        forged_inputs[1][0] += BlsFr::one();
+        assert_eq!(verify(&key, vk, &forged_inputs, &aggregate), Err(VotingError::InvalidAggregate));
+        let mut forged = aggregate.clone();
+        forged.c = (forged.c + G1Affine::generator()).into_affine();
+        assert_eq!(verify(&key, vk, &inputs, &forged), Err(VotingError::InvalidAggregate));
+        assert_eq!(verify(&key, vk, &inputs[..2], &aggregate), Err(VotingError::InvalidAggregate));
+    }
+}
@@ -0,0 +1,231 @@
+// import types from libaries and modules
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{prepare_verifying_key, Groth16, PreparedVerifyingKey, ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use ark_snark::CircuitSpecificSetupSNARK;
+use ark_std::rand::thread_rng;
+use sha3::{Digest, Sha3_256};
+use voting_core::backend::{Nullifier, ProofBackend, ProofMetrics};
+use voting_core::ballot::Ballot;
+use voting_core::error::VotingError;
+use crate::circuit::VotingCircuit;
+use crate::curve::{CircuitField, SnarkCurve};
+use crate::election::{Election, ElectionInput, ManifestError};
+use crate::merkle_tree::{MerklePath, TREE_DEPTH};
+use crate::vote_proof::{self, SnarkVote, VoteOpening};
+use crate::voter_registration::Voter;
+
+// public parameters of the zk-SNARK backend for one election
+pub struct SnarkParams<E: SnarkCurve> {
+    pub pk: ProvingKey<E>, // proving key
+    pub vk: VerifyingKey<E>, // verifiying key
+    pub pvk: PreparedVerifyingKey<E>, // verifying key with its pairing terms computed once for every ballot
+    pub root: E::ScalarField, // published root of the voter registry
+}
+
+// hash of an election's verifying key, published so every process can check the keys it loads
+pub type Fingerprint = [u8; 32];
+
+// zk-SNARK parameter implementation
+impl<E: SnarkCurve> SnarkParams<E> {
+
+    // function to wrap a proving key, preparing the verifying key it carries
+    pub fn new(pk: ProvingKey<E>, root: E::ScalarField) -> Self {
+        Self { vk: pk.vk.clone(), pvk: prepare_verifying_key(&pk.vk), pk, root }
+    }
+
+    // function to compute the fingerprint of the verifying key, bound to the election manifest
+    pub fn fingerprint(&self, election: &Election) -> Result<Fingerprint, VotingError> {
+        let mut bytes = Vec::new();
+        self.vk.serialize_compressed(&mut bytes).map_err(|error| VotingError::Serialization(error.to_string()))?;
+        let mut hash = Sha3_256::new();
+        hash.update(b"groth16-vk");
+        hash.update(election.manifest_hash);
+        hash.update(bytes);
+        Ok(hash.finalize().into())
+    }
+
+    // function to write the proving and verifying keys to a directory, returning the fingerprint to publish
+    pub fn save_keys(&self, dir: &Path, election: &Election) -> Result<Fingerprint, VotingError> {
+        std::fs::create_dir_all(dir).map_err(|error| VotingError::KeyStorage(error.to_string()))?;
+        let (pk_path, vk_path) = key_paths(dir, election);
+        write_key(&pk_path, &self.pk)?;
+        write_key(&vk_path, &self.vk)?;
+        self.fingerprint(election)
+    }
+
+    // function to read the keys written by save_keys, checking every point and the published fingerprint
+    pub fn load_keys(dir: &Path, election: &Election, root: E::ScalarField, fingerprint: &Fingerprint) -> Result<Self, VotingError> {
+        let (pk_path, vk_path) = key_paths(dir, election);
+        let pk: ProvingKey<E> = read_key(&pk_path)?;
+        let vk: VerifyingKey<E> = read_key(&vk_path)?;
+
+        // the proving key carries its own copy of the verifying key, both must be the published one
+        if pk.vk != vk {
+            return Err(VotingError::KeyMismatch);
+        }
+        let params = Self::new(pk, root);
+        if params.fingerprint(election)? != *fingerprint {
+            return Err(VotingError::KeyMismatch);
+        }
+        Ok(params)
+    }
+
+}
+
+// function to name the key files of an election
+fn key_paths(dir: &Path, election: &Election) -> (PathBuf, PathBuf) {
+    (dir.join(format!("{}.pk", election.id)), dir.join(format!("{}.vk", election.id)))
+}
+
+// function to write one key or transcript in compressed form
+pub(crate) fn write_key<K: CanonicalSerialize>(path: &Path, key: &K) -> Result<(), VotingError> {
+    let file = File::create(path).map_err(|error| VotingError::KeyStorage(error.to_string()))?;
+    key.serialize_compressed(BufWriter::new(file)).map_err(|error| VotingError::Serialization(error.to_string()))
+}
+
+// function to read one key or transcript, rejecting points that are off the curve or outside the prime order subgroup
+pub(crate) fn read_key<K: CanonicalDeserialize>(path: &Path) -> Result<K, VotingError> {
+    let file = File::open(path).map_err(|error| VotingError::KeyStorage(error.to_string()))?;
+    K::deserialize_with_mode(BufReader::new(file), Compress::Yes, Validate::Yes).map_err(|error| VotingError::Serialization(error.to_string()))
+}
+
+// zk-SNARK backend implementation
+impl<E: SnarkCurve> ProofBackend for SnarkVote<E> {
+    type Setup = E::ScalarField; // published root of the voter registry
+    type Params = SnarkParams<E>;
+    type Witness = (Ballot, MerklePath<E::ScalarField>); // ballot and the voter's registry path
+    type Opening = VoteOpening<E::ScalarField>;
+
+    // function to run the trusted setup for a circuit shaped by the election's candidate count
+    fn setup(election: &Election, root: E::ScalarField) -> Result<SnarkParams<E>, VotingError> {
+
+        let mut rng = thread_rng();
+
+        // the keys only fit the curve the manifest names
+        if election.curve != E::Fr::CURVE {
+            return Err(ManifestError::CurveMismatch(election.curve, E::Fr::CURVE).into());
+        }
+
+        // the circuit is compiled for a fixed registry depth
+        if election.registry_depth != TREE_DEPTH {
+            return Err(ManifestError::UnsupportedRegistryDepth(election.registry_depth, TREE_DEPTH).into());
+        }
+
+        // generate proving and verification keys
+        let (pk, _) = Groth16::<E>::setup(VotingCircuit::empty(election.num_candidates()), &mut rng)
+            .map_err(|error| VotingError::SetupFailed(error.to_string()))?;
+
+        Ok(SnarkParams::new(pk, root))
+
+    }
+
+    // function to prove a candidate choice, contests are not part of the circuit
+    fn prove(params: &SnarkParams<E>, election: &Election, voter: &Voter, (ballot, path): (Ballot, MerklePath<E::ScalarField>)) -> Result<(Self, VoteOpening<E::ScalarField>), VotingError> {
+        if ballot.candidate >= election.num_candidates() {
+            return Err(VotingError::InvalidCandidate { candidate: ballot.candidate, num_candidates: election.num_candidates() });
+        }
+        if !ballot.answers.is_empty() {
+            return Err(VotingError::InvalidAnswers);
+        }
+        SnarkVote::new(ballot.candidate, voter, &path, election, &params.pk)
+    }
+
+    // function for proof verification
+    fn verify(&mut self, params: &SnarkParams<E>, election: &Election) -> Result<(), VotingError> {
+
+        // verification time initialization
+        let verify_start = Instant::now();
+
+        // verify the proof against the prepared key
+        let result = self.verify_prepared(params, election);
+
+        // measure verification time
+        self.verify_time = verify_start.elapsed().as_nanos();
+        result
+
+    }
+
+    // function to verify every proof with one pairing product
+    fn batch_verify(params: &SnarkParams<E>, election: &Election, votes: &[&Self]) -> bool {
+        vote_proof::batch_verify(&params.pvk, params.root, election.field_id(), votes)
+    }
+
+    // function to serialize the public inputs carried by the vote and its proof
+    fn serialize(&self) -> Result<Vec<u8>, VotingError> {
+        let mut bytes = Vec::new();
+        (self.nullifier, self.commitment).serialize_compressed(&mut bytes)
+            .and_then(|_| self.proof.serialize_compressed(&mut bytes))
+            .map_err(|error| VotingError::Serialization(error.to_string()))?;
+        Ok(bytes)
+    }
+
+    fn nullifier(&self) -> Nullifier {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&self.nullifier.into_bigint().to_bytes_le());
+        bytes
+    }
+
+    fn metrics(&self) -> ProofMetrics {
+        ProofMetrics {
+            gen_time: self.gen_time,
+            verify_time: self.verify_time,
+            proof_size: self.proof_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr as BlsFr};
+    use ark_bn254::Bn254;
+    use voting_core::ballot_box::BallotBox;
+    use voting_core::election::Curve;
+    use crate::voter_registration::RegistrationAuthority;
+
+    #[test]
+    fn saved_keys_verify_ballots_in_another_process() {
+        let election = Election::new("election_1".to_string(), vec!["a".to_string(), "b".to_string()], Vec::new(), 8).unwrap();
+        let voter = Voter::new("voter".to_string());
+        let mut authority = RegistrationAuthority::<BlsFr>::new();
+        let index = authority.register(&voter).unwrap();
+        let path = authority.path(index).unwrap();
+        let dir = std::env::temp_dir().join(format!("snark_keys_{}", std::process::id()));
+
+        // the prover runs the setup and publishes the fingerprint
+        let prover = BallotBox::<SnarkVote<Bls12_381>>::setup(election.clone(), authority.root()).unwrap();
+        let fingerprint = prover.params.save_keys(&dir, &election).unwrap();
+        let (vote, _) = prover.prove(&voter, (Ballot::new(1, &[]), path)).unwrap();
+
+        // the verifier only has the files and the fingerprint
+        let params = SnarkParams::<Bls12_381>::load_keys(&dir, &election, authority.root(), &fingerprint).unwrap();
+        let mut verifier = BallotBox::new(election.clone(), params);
+        assert_eq!(verifier.submit(vote), Ok(()));
+
+        // keys for another election or with a damaged file are refused
+        let mut other = fingerprint;
+        other[0] ^= 1;
+        assert_eq!(SnarkParams::<Bls12_381>::load_keys(&dir, &election, authority.root(), &other).err(), Some(VotingError::KeyMismatch));
+
+        let (_, vk_path) = key_paths(&dir, &election);
+        let mut bytes = std::fs::read(&vk_path).unwrap();
+        bytes[0] ^= 1;
+        std::fs::write(&vk_path, bytes).unwrap();
+        assert!(matches!(SnarkParams::<Bls12_381>::load_keys(&dir, &election, authority.root(), &fingerprint), Err(VotingError::Serialization(_))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keys_are_only_generated_on_the_manifest_curve() {
+        let election = Election::new("election_1".to_string(), vec!["a".to_string(), "b".to_string()], Vec::new(), 8).unwrap();
+
+        // keys for a bls12-381 election cannot be generated on another curve
+        let result = BallotBox::<SnarkVote<Bn254>>::setup(election, ark_bn254::Fr::from(0));
+        assert_eq!(result.err(), Some(ManifestError::CurveMismatch(Curve::Bls12_381, Curve::Bn254).into()));
+    }
+}
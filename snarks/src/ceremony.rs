@@ -0,0 +1,335 @@
+// import types from libaries and modules
+use std::path::Path;
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, One};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, OptimizationGoal, SynthesisMode};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::{thread_rng, CryptoRng, RngCore}, UniformRand};
+use sha3::{Digest, Sha3_256};
+use voting_core::error::VotingError;
+use crate::backend::{self, Fingerprint, SnarkParams};
+use crate::circuit::VotingCircuit;
+use crate::curve::SnarkCurve;
+use crate::election::Election;
+use crate::powers_of_tau::{challenge, same_ratio, scale, PowersOfTau};
+
+// one participant's update of delta, with a proof that they know the factor they applied
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Contribution<E: Pairing> {
+    pub delta_g1: E::G1Affine, // delta in G1 after this contribution
+    pub s: E::G1Affine, // random base chosen by the participant
+    pub s_delta: E::G1Affine, // base multiplied by the participant's secret
+    pub r_delta: E::G2Affine, // transcript challenge multiplied by the participant's secret
+}
+
+// phase-two transcript of the Groth16 setup, passed from participant to participant as a file
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Ceremony<E: SnarkCurve> {
+    pub phase_one: PowersOfTau<E>, // phase-one transcript the starting parameters are derived from
+    pub initial: ProvingKey<E>, // parameters before any contribution, pinned by their published digest
+    pub current: ProvingKey<E>, // parameters after the latest contribution
+    pub contributions: Vec<Contribution<E>>, // every update in order
+}
+
+// ceremony implementation
+impl<E: SnarkCurve> Ceremony<E> {
+
+    // function to start a ceremony for the election's circuit from a verified phase-one transcript
+    pub fn new(election: &Election, phase_one: PowersOfTau<E>) -> Result<Self, VotingError> {
+        phase_one.verify()?;
+        let pk = initial_parameters(&phase_one, election)?;
+        Ok(Self { phase_one, initial: pk.clone(), current: pk, contributions: Vec::new() })
+    }
+
+    // function to hash the starting parameters, published by the coordinator so participants can check the file they receive
+    pub fn initial_digest(&self) -> Result<Fingerprint, VotingError> {
+        let mut bytes = Vec::new();
+        self.initial.serialize_compressed(&mut bytes).map_err(|error| VotingError::Serialization(error.to_string()))?;
+        let mut hash = Sha3_256::new();
+        hash.update(b"groth16-phase2-initial");
+        hash.update(bytes);
+        Ok(hash.finalize().into())
+    }
+
+    // function to multiply delta by a fresh secret, dividing the queries that depend on it, and prove the update
+    pub fn contribute<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Result<(), VotingError> {
+
+        // the secret is dropped once the update is applied
+        let secret = E::ScalarField::rand(rng);
+        let inverse = secret.inverse().ok_or_else(|| VotingError::SetupFailed("zero contribution".to_string()))?;
+
+        // proof of knowledge of the secret, bound to the delta it updates
+        let s = E::G1::rand(rng).into_affine();
+        let s_delta = (s * secret).into_affine();
+        let r = challenge::<E>(b"groth16-phase2-contribution", &self.current.delta_g1, &s, &s_delta)?;
+        let r_delta = (r * secret).into_affine();
+
+        // update delta and every query element divided by it
+        let pk = &mut self.current;
+        pk.delta_g1 = (pk.delta_g1 * secret).into_affine();
+        pk.vk.delta_g2 = (pk.vk.delta_g2 * secret).into_affine();
+        pk.h_query = scale(&pk.h_query, inverse);
+        pk.l_query = scale(&pk.l_query, inverse);
+
+        self.contributions.push(Contribution { delta_g1: pk.delta_g1, s, s_delta, r_delta });
+        Ok(())
+
+    }
+
+    // function to check that the starting parameters are the published ones, every contribution, and that the final parameters follow from them
+    pub fn verify(&self, pinned: &Fingerprint) -> Result<(), VotingError> {
+
+        // starting parameters other than the published ones are blamed on the coordinator
+        if self.initial_digest()? != *pinned {
+            return Err(VotingError::InvalidContribution(0));
+        }
+        let initial = &self.initial;
+
+        // the final parameters are blamed on the last participant
+        let last = VotingError::InvalidContribution(self.contributions.len());
+        let current = &self.current;
+
+        // only delta and the queries divided by it may change
+        if current.vk.alpha_g1 != initial.vk.alpha_g1
+            || current.vk.beta_g2 != initial.vk.beta_g2
+            || current.vk.gamma_g2 != initial.vk.gamma_g2
+            || current.vk.gamma_abc_g1 != initial.vk.gamma_abc_g1
+            || current.beta_g1 != initial.beta_g1
+            || current.a_query != initial.a_query
+            || current.b_g1_query != initial.b_g1_query
+            || current.b_g2_query != initial.b_g2_query
+            || current.h_query.len() != initial.h_query.len()
+            || current.l_query.len() != initial.l_query.len() {
+            return Err(last);
+        }
+
+        // each participant knows the secret relating s to s_delta, and applied the same one to delta
+        let mut previous = initial.delta_g1;
+        for (i, contribution) in self.contributions.iter().enumerate() {
+            let r = challenge::<E>(b"groth16-phase2-contribution", &previous, &contribution.s, &contribution.s_delta)?;
+            if contribution.s.is_zero()
+                || !same_ratio::<E>((contribution.s, contribution.s_delta), (r, contribution.r_delta))
+                || !same_ratio::<E>((previous, contribution.delta_g1), (r, contribution.r_delta)) {
+                return Err(VotingError::InvalidContribution(i + 1));
+            }
+            previous = contribution.delta_g1;
+        }
+
+        // the final delta is the last contributed one, moved by the same total in both groups
+        if current.delta_g1 != previous || !same_ratio::<E>((initial.delta_g1, current.delta_g1), (initial.vk.delta_g2, current.vk.delta_g2)) {
+            return Err(last);
+        }
+
+        // the queries were divided by the same total, checked on random combinations of their elements
+        let mut rng = thread_rng();
+        for (old, new) in [(&initial.h_query, &current.h_query), (&initial.l_query, &current.l_query)] {
+            let weights: Vec<E::ScalarField> = (0..old.len()).map(|_| E::ScalarField::rand(&mut rng)).collect();
+            let old_sum = E::G1::msm(old, &weights).map_err(|_| last.clone())?.into_affine();
+            let new_sum = E::G1::msm(new, &weights).map_err(|_| last.clone())?.into_affine();
+            if !same_ratio::<E>((new_sum, old_sum), (initial.vk.delta_g2, current.vk.delta_g2)) {
+                return Err(last);
+            }
+        }
+
+        Ok(())
+
+    }
+
+    // function to accept the final keys once the starting parameters are re-derived from phase one and the whole transcript verifies
+    pub fn finish(self, election: &Election, root: E::ScalarField) -> Result<SnarkParams<E>, VotingError> {
+        if self.contributions.is_empty() {
+            return Err(VotingError::SetupFailed("the ceremony has no contributions".to_string()));
+        }
+        self.phase_one.verify()?;
+        if initial_parameters(&self.phase_one, election)? != self.initial {
+            return Err(VotingError::InvalidContribution(0));
+        }
+        self.verify(&self.initial_digest()?)?;
+        Ok(SnarkParams::new(self.current, root))
+    }
+
+    // function to write the transcript for the next participant
+    pub fn save(&self, path: &Path) -> Result<(), VotingError> {
+        backend::write_key(path, self)
+    }
+
+    // function to read a transcript written by the previous participant, checking every point
+    pub fn load(path: &Path) -> Result<Self, VotingError> {
+        backend::read_key(path)
+    }
+
+}
+
+// function to synthesize the election's circuit without a witness, the way the Groth16 generator does
+fn synthesize<E: SnarkCurve>(election: &Election) -> Result<ConstraintSystemRef<E::ScalarField>, VotingError> {
+    let cs = ConstraintSystem::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    cs.set_mode(SynthesisMode::Setup);
+    VotingCircuit::empty(election.num_candidates()).generate_constraints(cs.clone())
+        .map_err(|error| VotingError::SetupFailed(error.to_string()))?;
+    cs.finalize();
+    Ok(cs)
+}
+
+// function to return the size of the evaluation domain, and so of the phase-one transcript, the election's circuit needs
+pub fn domain_size<E: SnarkCurve>(election: &Election) -> Result<usize, VotingError> {
+    let cs = synthesize::<E>(election)?;
+    GeneralEvaluationDomain::<E::ScalarField>::new(cs.num_constraints() + cs.num_instance_variables())
+        .map(|domain| domain.size())
+        .ok_or_else(|| VotingError::SetupFailed("the circuit is too large for the scalar field".to_string()))
+}
+
+// function to derive the Groth16 parameters with gamma and delta at the generator from phase one, following BGM17
+fn initial_parameters<E: SnarkCurve>(phase_one: &PowersOfTau<E>, election: &Election) -> Result<ProvingKey<E>, VotingError> {
+
+    let cs = synthesize::<E>(election)?;
+    let matrices = cs.to_matrices().ok_or_else(|| VotingError::SetupFailed("the constraint system keeps no matrices".to_string()))?;
+    let (num_constraints, num_instance) = (cs.num_constraints(), cs.num_instance_variables());
+    let domain = GeneralEvaluationDomain::<E::ScalarField>::new(num_constraints + num_instance)
+        .ok_or_else(|| VotingError::SetupFailed("the circuit is too large for the scalar field".to_string()))?;
+    let size = domain.size();
+    if phase_one.size() < size {
+        return Err(VotingError::SetupFailed(format!("the circuit needs {} powers of tau, phase one has {}", size, phase_one.size())));
+    }
+
+    // the inverse fft of the first n powers gives every lagrange polynomial of the domain evaluated at tau
+    let tau_g1 = lagrange(&domain, &phase_one.tau_g1);
+    let alpha_g1 = lagrange(&domain, &phase_one.alpha_tau_g1);
+    let beta_g1 = lagrange(&domain, &phase_one.beta_tau_g1);
+    let tau_g2 = lagrange(&domain, &phase_one.tau_g2);
+
+    // each variable's a, b and c polynomials at tau, with the public inputs copied into the rows after the constraints as in ark-groth16
+    let num_variables = num_instance + cs.num_witness_variables();
+    let mut a_columns = transpose(&matrices.a, num_variables);
+    for (i, column) in a_columns.iter_mut().take(num_instance).enumerate() {
+        column.push((num_constraints + i, E::ScalarField::one()));
+    }
+    let b_columns = transpose(&matrices.b, num_variables);
+    let c_columns = transpose(&matrices.c, num_variables);
+    let a = at_tau(&tau_g1, &a_columns);
+    let b_g1 = at_tau(&tau_g1, &b_columns);
+    let b_g2 = at_tau(&tau_g2, &b_columns);
+
+    let combined: Vec<E::G1> = at_tau(&beta_g1, &a_columns).into_iter()
+        .zip(at_tau(&alpha_g1, &b_columns))
+        .zip(at_tau(&tau_g1, &c_columns))
+        .map(|((a, b), c)| a + b + c)
+        .collect();
+
+    // h_query holds tau^i times the vanishing polynomial tau^n - 1, for i < n - 1
+    let h_query: Vec<E::G1> = (0..size - 1).map(|i| phase_one.tau_g1[i + size].into_group() - phase_one.tau_g1[i]).collect();
+
+    // gamma and delta start at the generator, so beta * a + alpha * b + c is not divided by either
+    let combined = E::G1::normalize_batch(&combined);
+    Ok(ProvingKey {
+        vk: VerifyingKey {
+            alpha_g1: phase_one.alpha_tau_g1[0],
+            beta_g2: phase_one.beta_g2,
+            gamma_g2: E::G2Affine::generator(),
+            delta_g2: E::G2Affine::generator(),
+            gamma_abc_g1: combined[..num_instance].to_vec(),
+        },
+        beta_g1: phase_one.beta_tau_g1[0],
+        delta_g1: E::G1Affine::generator(),
+        a_query: E::G1::normalize_batch(&a),
+        b_g1_query: E::G1::normalize_batch(&b_g1),
+        b_g2_query: E::G2::normalize_batch(&b_g2),
+        h_query: E::G1::normalize_batch(&h_query),
+        l_query: combined[num_instance..].to_vec(),
+    })
+
+}
+
+// function to evaluate every lagrange polynomial of the domain at tau from the first n powers of tau
+fn lagrange<G: AffineRepr>(domain: &GeneralEvaluationDomain<G::ScalarField>, powers: &[G]) -> Vec<G> {
+    let points: Vec<G::Group> = powers[..domain.size()].iter().map(|point| point.into_group()).collect();
+    G::Group::normalize_batch(&domain.ifft(&points))
+}
+
+// function to list, for each variable, the rows of a matrix it appears in with its coefficient there
+fn transpose<F: Copy>(matrix: &[Vec<(F, usize)>], num_variables: usize) -> Vec<Vec<(usize, F)>> {
+    let mut columns = vec![Vec::new(); num_variables];
+    for (row, terms) in matrix.iter().enumerate() {
+        for (coefficient, variable) in terms {
+            columns[*variable].push((row, *coefficient));
+        }
+    }
+    columns
+}
+
+// function to evaluate each variable's polynomial at tau, one multi-scalar multiplication over the rows it appears in
+fn at_tau<G: AffineRepr>(basis: &[G], columns: &[Vec<(usize, G::ScalarField)>]) -> Vec<G::Group> {
+    columns.iter().map(|column| {
+        let (points, coefficients): (Vec<G>, Vec<G::ScalarField>) = column.iter().map(|(row, coefficient)| (basis[*row], *coefficient)).unzip();
+        G::Group::msm_unchecked(&points, &coefficients)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr as BlsFr, G1Affine, G2Affine};
+    use voting_core::ballot::Ballot;
+    use voting_core::ballot_box::BallotBox;
+    use crate::vote_proof::SnarkVote;
+    use crate::vote_submission::VoteSubmission;
+    use crate::voter_registration::{RegistrationAuthority, Voter};
+
+    #[test]
+    fn only_verified_transcripts_produce_keys() {
+        let election = Election::new("election_1".to_string(), vec!["a".to_string(), "b".to_string()], Vec::new(), 8).unwrap();
+        let voter = Voter::new("voter".to_string());
+        let mut authority = RegistrationAuthority::<BlsFr>::new();
+        let index = authority.register(&voter).unwrap();
+        let path = authority.path(index).unwrap();
+
+        // a phase one nobody contributed to has a tau everyone knows and is refused
+        let no_contributions = Some(VotingError::SetupFailed("phase one has no contributions".to_string()));
+        let uncontributed = PowersOfTau::<Bls12_381>::new(domain_size::<Bls12_381>(&election).unwrap());
+        assert_eq!(Ceremony::<Bls12_381>::new(&election, uncontributed.clone()).err(), no_contributions);
+
+        // one participant runs phase one, two contribute to phase two in turn
+        let mut phase_one = uncontributed.clone();
+        phase_one.contribute(&mut thread_rng()).unwrap();
+        let mut ceremony = Ceremony::<Bls12_381>::new(&election, phase_one).unwrap();
+        let pinned = ceremony.initial_digest().unwrap();
+        assert!(ceremony.clone().finish(&election, authority.root()).is_err());
+        ceremony.contribute(&mut thread_rng()).unwrap();
+        ceremony.contribute(&mut thread_rng()).unwrap();
+        assert_eq!(ceremony.verify(&pinned), Ok(()));
+
+        // a participant who sets delta to a value they chose cannot prove the update
+        let mut forged = ceremony.clone();
+        forged.current.delta_g1 = (G1Affine::generator() * BlsFr::from(5)).into_affine();
+        forged.current.vk.delta_g2 = (G2Affine::generator() * BlsFr::from(5)).into_affine();
+        forged.contributions[1].delta_g1 = forged.current.delta_g1;
+        assert_eq!(forged.verify(&pinned), Err(VotingError::InvalidContribution(2)));
+
+        // a query element changed outside of a contribution is caught
+        let mut tampered = ceremony.clone();
+        tampered.current.h_query[0] = (tampered.current.h_query[0] + G1Affine::generator()).into_affine();
+        assert_eq!(tampered.verify(&pinned), Err(VotingError::InvalidContribution(2)));
+
+        // starting parameters that do not follow from phase one fail the published digest, and fail finishing under their own
+        let mut tampered = ceremony.clone();
+        tampered.initial.a_query[1] = (tampered.initial.a_query[1] * BlsFr::from(2)).into_affine();
+        tampered.current.a_query[1] = tampered.initial.a_query[1];
+        assert_eq!(tampered.verify(&pinned), Err(VotingError::InvalidContribution(0)));
+        assert_eq!(tampered.verify(&tampered.initial_digest().unwrap()), Ok(()));
+        assert_eq!(tampered.finish(&election, authority.root()).err(), Some(VotingError::InvalidContribution(0)));
+
+        // neither can a transcript whose phase one was swapped for an uncontributed one be finished
+        let mut swapped = ceremony.clone();
+        swapped.phase_one = uncontributed;
+        assert_eq!(swapped.clone().finish(&election, authority.root()).err(), no_contributions);
+        assert_eq!(VoteSubmission::from_ceremony(election.clone(), authority.root(), swapped).err(), no_contributions);
+
+        // the ceremony keys prove and verify ballots
+        let params = ceremony.finish(&election, authority.root()).unwrap();
+        let mut ballot_box = BallotBox::<SnarkVote<Bls12_381>>::new(election, params);
+        let (vote, _) = ballot_box.prove(&voter, (Ballot::new(1, &[]), path)).unwrap();
+        assert_eq!(ballot_box.submit(vote), Ok(()));
+    }
+}
@@ -1,50 +1,228 @@
 // arkworks libraries
-use ark_bls12_381::Fr as BlsFr;
-use ark_relations::{lc, r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},};
+use ark_crypto_primitives::crh::poseidon::constraints::CRHParametersVar;
+use ark_r1cs_std::{alloc::AllocVar, boolean::Boolean, eq::EqGadget, fields::{fp::FpVar, FieldVar}, select::CondSelectGadget};
+use ark_relations::{ns, r1cs::{ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError, SynthesisMode}};
+use ark_std::rand::thread_rng;
+use voting_core::error::VotingError;
+use crate::curve::CircuitField;
+use crate::election::{Election, ElectionInput};
+use crate::merkle_tree::{MerklePath, TREE_DEPTH};
+use crate::poseidon::{self, COMMITMENT_DOMAIN, LEAF_DOMAIN, NULLIFIER_DOMAIN};
+use crate::vote_proof::VoteOpening;
+use crate::voter_registration::{RegistrationAuthority, Voter, VoterCredential};
+
+// name and index of the first constraint of each part of the circuit, in synthesis order
+pub type Sections = Vec<(&'static str, usize)>;
+
+// function-like macro to open a named namespace and record where its constraints start
+macro_rules! section {
+    ($cs:expr, $sections:expr, $name:literal) => {{
+        $sections.push(($name, $cs.num_constraints()));
+        ns!($cs, $name)
+    }};
+}
 
 // circuit definition
 #[derive(Clone)]
-pub struct VotingCircuit {
-    pub vote: Option<BlsFr>, //witness
-    pub nullifier_hash: Option<BlsFr>,
-    pub blinding: Option<BlsFr>,
+pub struct VotingCircuit<F: CircuitField> {
+    pub vote: Option<F>, //witness
+    pub nullifier_hash: Option<F>,
+    pub commitment: Option<F>, // published commitment to the vote
+    pub blinding: Option<F>, // commitment randomness (witness)
+    pub root: Option<F>, // published registry root
+    pub election_id: Option<F>, // identifier of the election being voted in
+    pub secret: Option<F>, // voter secret (witness)
+    pub path: Option<MerklePath<F>>, // authentication path of the voter leaf (witness)
+    pub num_candidates: usize, // votes must lie in [0, num_candidates)
+}
+
+// circuit implementation
+impl<F: CircuitField> VotingCircuit<F> {
+
+    // function to build a circuit with the shape of an election and no assignments, used for key generation
+    pub fn empty(num_candidates: usize) -> Self {
+        Self {
+            vote: None,
+            nullifier_hash: None,
+            commitment: None,
+            blinding: None,
+            root: None,
+            election_id: None,
+            secret: None,
+            path: None,
+            num_candidates,
+        }
+    }
+
+}
+
+// function to count the constraints of the circuit over a field for an election with n candidates
+pub fn constraint_count<F: CircuitField>(num_candidates: usize) -> Option<usize> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    VotingCircuit::<F>::empty(num_candidates).generate_constraints(cs.clone()).ok()?;
+    Some(cs.num_constraints())
+}
+
+// function to build a circuit whose witness is a registered voter choosing the given candidate
+pub fn sample_circuit<F: CircuitField>(election: &Election, candidate: u64) -> Result<VotingCircuit<F>, VotingError> {
+
+    // one voter in an otherwise empty registry
+    let voter = Voter::new("voter".to_string());
+    let mut authority = RegistrationAuthority::<F>::new();
+    let index = authority.register(&voter)?;
+    let path = authority.path(index)?;
+    let election_id = election.field_id();
+    let opening = VoteOpening { candidate, blinding: F::rand(&mut thread_rng()) };
+
+    Ok(VotingCircuit {
+        vote: Some(F::from(candidate)),
+        nullifier_hash: Some(voter.nullifier(election_id)),
+        commitment: Some(opening.commitment()),
+        blinding: Some(opening.blinding),
+        root: Some(authority.root()),
+        election_id: Some(election_id),
+        secret: Some(voter.secret()),
+        path: Some(path),
+        num_candidates: election.num_candidates(),
+    })
+
 }
 
 // create constraints for proof generation and verification
-impl ConstraintSynthesizer<BlsFr> for VotingCircuit {
-    fn generate_constraints(self, cs: ConstraintSystemRef<BlsFr>) -> Result<(), SynthesisError> {
+impl<F: CircuitField> ConstraintSynthesizer<F> for VotingCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.synthesize(cs, &mut Sections::new())
+    }
+}
+
+// circuit synthesis implementation
+impl<F: CircuitField> VotingCircuit<F> {
+
+    // function to create the constraints, recording the section each one belongs to
+    pub fn synthesize(self, cs: ConstraintSystemRef<F>, sections: &mut Sections) -> Result<(), SynthesisError> {
+
+        // an election needs at least one candidate
+        if self.num_candidates == 0 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
 
         // create variables for constraints
-        let vote_var = cs.new_witness_variable(|| self.vote.ok_or(SynthesisError::AssignmentMissing))?;
-        let _nullifier_var = cs.new_input_variable(|| self.nullifier_hash.ok_or(SynthesisError::AssignmentMissing))?;
-        let _blinding_var = cs.new_input_variable(|| self.blinding.ok_or(SynthesisError::AssignmentMissing))?;
-        let one = BlsFr::from(1u64);
-        let two = BlsFr::from(2u64);
-        let three = BlsFr::from(3u64);
-
-        // constraint one
-        let product1 = cs.new_witness_variable(|| Ok(self.vote.unwrap() * (self.vote.unwrap() - one)))?;
-        cs.enforce_constraint(
-            lc!() + vote_var,
-            lc!() + vote_var - (one, Variable::One),
-            lc!() + product1,
-        )?;
-
-        // constraint two
-        let product2 = cs.new_witness_variable(|| Ok((self.vote.unwrap() - two) * (self.vote.unwrap() - three)))?;
-        cs.enforce_constraint(
-            lc!() + vote_var - (two, Variable::One),
-            lc!() + vote_var - (three, Variable::One),
-            lc!() + product2,
-        )?;
-
-        // constraint three; ensure votes are a valid option
-        cs.enforce_constraint(
-            lc!() + product1,
-            lc!() + product2,
-            lc!(),
-        )?;
+        let vote_var = FpVar::new_witness(cs.clone(), || self.vote.ok_or(SynthesisError::AssignmentMissing))?;
+        let nullifier_var = FpVar::new_input(cs.clone(), || self.nullifier_hash.ok_or(SynthesisError::AssignmentMissing))?;
+        let commitment_var = FpVar::new_input(cs.clone(), || self.commitment.ok_or(SynthesisError::AssignmentMissing))?;
+        let root_var = FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let election_var = FpVar::new_input(cs.clone(), || self.election_id.ok_or(SynthesisError::AssignmentMissing))?;
+        let secret_var = FpVar::new_witness(cs.clone(), || self.secret.ok_or(SynthesisError::AssignmentMissing))?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || self.blinding.ok_or(SynthesisError::AssignmentMissing))?;
+        let params = CRHParametersVar::new_constant(cs.clone(), poseidon::config())?;
+
+        // constraints one to n; ensure votes are a valid option, prod(v - i) over every candidate i is zero
+        {
+            let _ns = section!(cs, sections, "candidate_range");
+            let mut product = vote_var.clone();
+            for candidate in 1..self.num_candidates {
+                product *= &vote_var - F::from(candidate as u64);
+            }
+            product.enforce_equal(&FpVar::zero())?;
+        }
+
+        // registered leaf derived from the voter secret
+        {
+            let _ns = section!(cs, sections, "registry_membership");
+            let mut node = poseidon::hash_gadget(&params, &[FpVar::constant(F::from(LEAF_DOMAIN)), secret_var.clone()])?;
+
+            // hash up the authentication path, ordering each pair by the direction bit
+            for level in 0..TREE_DEPTH {
+                let sibling = FpVar::new_witness(cs.clone(), || {
+                    self.path.as_ref().and_then(|p| p.siblings.get(level).copied()).ok_or(SynthesisError::AssignmentMissing)
+                })?;
+                let is_right = Boolean::new_witness(cs.clone(), || {
+                    self.path.as_ref().and_then(|p| p.directions.get(level).copied()).ok_or(SynthesisError::AssignmentMissing)
+                })?;
+                let left = FpVar::conditionally_select(&is_right, &sibling, &node)?;
+                let right = FpVar::conditionally_select(&is_right, &node, &sibling)?;
+                node = poseidon::hash_gadget(&params, &[left, right])?;
+            }
+
+            // the leaf must sit under the published root
+            node.enforce_equal(&root_var)?;
+        }
+
+        // the nullifier must come from the same secret and this election
+        {
+            let _ns = section!(cs, sections, "nullifier");
+            let nullifier = poseidon::hash_gadget(&params, &[FpVar::constant(F::from(NULLIFIER_DOMAIN)), secret_var, election_var])?;
+            nullifier.enforce_equal(&nullifier_var)?;
+        }
+
+        // the published commitment must hide this vote
+        {
+            let _ns = section!(cs, sections, "commitment");
+            let commitment = poseidon::hash_gadget(&params, &[FpVar::constant(F::from(COMMITMENT_DOMAIN)), vote_var, blinding_var])?;
+            commitment.enforce_equal(&commitment_var)?;
+        }
 
         Ok(())
     }
-}
\ No newline at end of file
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr as BlsFr};
+    use ark_groth16::Groth16;
+    use ark_snark::CircuitSpecificSetupSNARK;
+    use ark_std::rand::thread_rng;
+    use crate::voter_registration::{RegistrationAuthority, Voter, VoterCredential};
+
+    // function to check whether a vote satisfies the circuit for n candidates
+    fn satisfied(vote: u64, num_candidates: usize) -> bool {
+        let voter = Voter::new("voter".to_string());
+        let mut authority = RegistrationAuthority::<BlsFr>::new();
+        let index = authority.register(&voter).unwrap();
+        let path = authority.path(index).unwrap();
+        let election = BlsFr::from(1);
+        let blinding = BlsFr::from(7);
+        let circuit = VotingCircuit {
+            vote: Some(BlsFr::from(vote)),
+            nullifier_hash: Some(voter.nullifier(election)),
+            commitment: Some(poseidon::hash(&[BlsFr::from(COMMITMENT_DOMAIN), BlsFr::from(vote), blinding])),
+            blinding: Some(blinding),
+            root: Some(authority.root()),
+            election_id: Some(election),
+            secret: Some(voter.secret()),
+            path: Some(path),
+            num_candidates,
+        };
+        let cs = ConstraintSystem::<BlsFr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn votes_are_limited_to_the_candidate_set() {
+        assert!(satisfied(0, 2) && satisfied(1, 2));
+        assert!(!satisfied(2, 2));
+        assert!(satisfied(11, 12));
+        assert!(!satisfied(12, 12));
+    }
+
+    #[test]
+    fn setup_runs_on_an_empty_circuit() {
+        let (pk, _) = Groth16::<Bls12_381>::setup(VotingCircuit::<BlsFr>::empty(3), &mut thread_rng()).unwrap();
+        assert_eq!(pk.vk.gamma_abc_g1.len(), 5);
+
+        // proving without assignments reports them missing instead of panicking
+        let cs = ConstraintSystem::<BlsFr>::new_ref();
+        assert_eq!(VotingCircuit::<BlsFr>::empty(3).generate_constraints(cs), Err(SynthesisError::AssignmentMissing));
+    }
+
+    #[test]
+    fn constraint_count_grows_with_candidates() {
+        let two = constraint_count::<BlsFr>(2).unwrap();
+        assert_eq!(constraint_count::<BlsFr>(12).unwrap(), two + 10);
+        assert!(constraint_count::<BlsFr>(0).is_none());
+    }
+}
@@ -0,0 +1,44 @@
+// import types from libaries and modules
+use std::sync::OnceLock;
+use ark_crypto_primitives::sponge::{poseidon::PoseidonConfig, Absorb};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use voting_core::election::Curve;
+use crate::poseidon;
+
+// scalar fields the voting circuit can be built over
+pub trait CircuitField: PrimeField + Absorb {
+
+    // curve whose scalar field this is
+    const CURVE: Curve;
+
+    // function to return the poseidon parameters for this field, generated once
+    fn poseidon_config() -> &'static PoseidonConfig<Self>;
+
+}
+
+// pairing curves the zk-SNARK backend can prove over
+pub trait SnarkCurve: Pairing<ScalarField = Self::Fr> {
+    type Fr: CircuitField; // scalar field of the circuit
+}
+
+// function-like macro to implement both traits for a curve, with the poseidon s-box exponent and partial rounds its field needs
+macro_rules! snark_curve {
+    ($engine:ty, $field:ty, $curve:expr, $alpha:expr, $partial_rounds:expr) => {
+        impl CircuitField for $field {
+            const CURVE: Curve = $curve;
+            fn poseidon_config() -> &'static PoseidonConfig<Self> {
+                static CONFIG: OnceLock<PoseidonConfig<$field>> = OnceLock::new();
+                CONFIG.get_or_init(|| poseidon::parameters($alpha, $partial_rounds))
+            }
+        }
+        impl SnarkCurve for $engine {
+            type Fr = $field;
+        }
+    };
+}
+
+// x^5 is not a permutation of the bls12-377 scalar field, so it uses x^17 with fewer partial rounds
+snark_curve!(ark_bls12_381::Bls12_381, ark_bls12_381::Fr, Curve::Bls12_381, 5, 57);
+snark_curve!(ark_bn254::Bn254, ark_bn254::Fr, Curve::Bn254, 5, 57);
+snark_curve!(ark_bls12_377::Bls12_377, ark_bls12_377::Fr, Curve::Bls12_377, 17, 31);
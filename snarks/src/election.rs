@@ -0,0 +1,34 @@
+// import types from libaries and modules
+use ark_ff::PrimeField;
+pub use voting_core::election::{Election, ManifestError};
+
+// circuit inputs derived from an election
+pub trait ElectionInput {
+
+    // function to return the public circuit input identifying this election, derived from the manifest
+    fn field_id<F: PrimeField>(&self) -> F;
+
+}
+
+// election input implementation
+impl ElectionInput for Election {
+
+    fn field_id<F: PrimeField>(&self) -> F {
+        F::from_le_bytes_mod_order(&self.manifest_hash)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr as BlsFr;
+
+    #[test]
+    fn election_input_follows_the_manifest() {
+        let candidates = |last: &str| vec!["a".to_string(), "b".to_string(), last.to_string()];
+        let election = Election::new("election_1".to_string(), candidates("c"), Vec::new(), 8).unwrap();
+        let renamed = Election::new("election_1".to_string(), candidates("d"), Vec::new(), 8).unwrap();
+        assert_ne!(election.field_id::<BlsFr>(), renamed.field_id::<BlsFr>());
+    }
+}
@@ -0,0 +1,113 @@
+// import types from libaries and modules
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+use voting_core::error::VotingError;
+use crate::circuit::{Sections, VotingCircuit};
+use crate::curve::CircuitField;
+
+// what a circuit compiles to for one witness
+pub struct CircuitReport {
+    pub constraints: usize, // number of constraints
+    pub witness_variables: usize, // private variables
+    pub public_inputs: usize, // public variables, without the constant one
+    pub sections: Vec<(&'static str, usize)>, // constraints in each named section
+    pub unsatisfied: Option<String>, // first constraint the witness breaks and its section
+}
+
+// function to synthesize a circuit, count what it compiles to and find the first constraint its witness breaks
+pub fn inspect<F: CircuitField>(circuit: VotingCircuit<F>) -> Result<(ConstraintSystemRef<F>, CircuitReport), VotingError> {
+
+    let cs = ConstraintSystem::<F>::new_ref();
+    let mut sections = Sections::new();
+    circuit.synthesize(cs.clone(), &mut sections).map_err(|error| VotingError::SetupFailed(error.to_string()))?;
+    cs.finalize();
+
+    // each section runs up to the start of the next one
+    let constraints = cs.num_constraints();
+    let sizes = sections.iter().enumerate()
+        .map(|(i, (name, start))| (*name, sections.get(i + 1).map_or(constraints, |next| next.1) - start))
+        .collect();
+
+    // without a tracing layer the trace is only the index, so it is named after its section
+    let unsatisfied = cs.which_is_unsatisfied().map_err(|error| VotingError::SetupFailed(error.to_string()))?
+        .map(|trace| match trace.parse::<usize>() {
+            Ok(index) => format!("constraint {} in {}", index, section_of(&sections, index)),
+            Err(_) => trace,
+        });
+
+    let report = CircuitReport {
+        constraints,
+        witness_variables: cs.num_witness_variables(),
+        public_inputs: cs.num_instance_variables() - 1,
+        sections: sizes,
+        unsatisfied,
+    };
+    Ok((cs, report))
+
+}
+
+// function to name the section a constraint index falls in
+fn section_of(sections: &Sections, index: usize) -> &'static str {
+    sections.iter().rev().find(|(_, start)| *start <= index).map_or("variable allocation", |(name, _)| name)
+}
+
+// function to write the A, B and C matrices of a synthesized circuit as text, one constraint per line
+pub fn export_matrices<F: PrimeField>(cs: &ConstraintSystemRef<F>, path: &Path) -> std::io::Result<()> {
+
+    let matrices = cs.to_matrices().ok_or_else(|| std::io::Error::other("the constraint system keeps no matrices"))?;
+    let mut file = BufWriter::new(File::create(path)?);
+
+    // variable 0 is the constant one, the public inputs follow and the witness variables come last
+    writeln!(file, "# {} constraints, {} instance variables including the constant one, {} witness variables",
+        matrices.num_constraints, matrices.num_instance_variables, matrices.num_witness_variables)?;
+    writeln!(file, "# each line is A * B = C, every term is variable:coefficient")?;
+    for (i, ((a, b), c)) in matrices.a.iter().zip(&matrices.b).zip(&matrices.c).enumerate() {
+        writeln!(file, "{}: [{}] * [{}] = [{}]", i, terms(a), terms(b), terms(c))?;
+    }
+    file.flush()
+
+}
+
+// function to format one row of a matrix, writing coefficients close to the modulus as negative numbers
+fn terms<F: PrimeField>(row: &[(F, usize)]) -> String {
+    row.iter().map(|(coefficient, variable)| {
+        let negated = -*coefficient;
+        if negated.into_bigint() < coefficient.into_bigint() {
+            format!("{}:-{}", variable, negated)
+        } else {
+            format!("{}:{}", variable, coefficient)
+        }
+    }).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr as BlsFr;
+    use crate::circuit;
+    use crate::election::Election;
+
+    #[test]
+    fn broken_witness_is_traced_to_its_section() {
+        let election = Election::new("election_1".to_string(), vec!["a".to_string(), "b".to_string()], Vec::new(), 8).unwrap();
+
+        // an honest vote satisfies every section
+        let (cs, report) = inspect(circuit::sample_circuit::<BlsFr>(&election, 1).unwrap()).unwrap();
+        assert_eq!(report.unsatisfied, None);
+        assert_eq!(report.public_inputs, 4);
+        assert_eq!(report.sections.iter().map(|(_, size)| size).sum::<usize>(), report.constraints);
+
+        // the matrices have one line per constraint after the header
+        let path = std::env::temp_dir().join(format!("voting_circuit_{}.r1cs", std::process::id()));
+        export_matrices(&cs, &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), report.constraints + 2);
+        std::fs::remove_file(&path).unwrap();
+
+        // a vote for a candidate that is not on the ballot breaks the range check
+        let (_, report) = inspect(circuit::sample_circuit::<BlsFr>(&election, 2).unwrap()).unwrap();
+        assert_eq!(report.unsatisfied.as_deref(), Some("constraint 1 in candidate_range"));
+    }
+}
@@ -1,85 +1,547 @@
 // import modules
+mod aggregation;
+mod backend;
+mod ceremony;
 mod circuit;
+mod curve;
+mod election;
+mod inspect;
+mod merkle_tree;
+mod plonk;
+mod poseidon;
+mod powers_of_tau;
 mod voter_registration;
 mod vote_proof;
 mod vote_submission;
 
 // import types from imported modules
-use voter_registration::{Voter, Party};
+use std::path::PathBuf;
+use std::time::Instant;
+use ark_bls12_377::Bls12_377;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_groth16::Groth16;
+use ark_serialize::CanonicalSerialize;
+use ark_snark::SNARK;
+use ark_std::rand::thread_rng;
+use aggregation::AggregationKey;
+use ceremony::Ceremony;
+use curve::SnarkCurve;
+use election::{Election, ElectionInput};
+use merkle_tree::MerkleTree;
+use powers_of_tau::PowersOfTau;
+use voter_registration::{RegistrationAuthority, Voter, VoterCredential};
 use vote_proof::SnarkVote;
 use vote_submission::VoteSubmission;
+use voting_core::election::Curve;
+use voting_core::error::VotingError;
+
+// function to report an error the simulation cannot continue from and exit with its code
+fn fail(context: &str, error: VotingError) -> ! {
+    println!("{}: {}", context, error);
+    std::process::exit(error.code());
+}
+
+// what the binary was asked to do
+enum Command {
+    Simulate, // run the election, `snarks [manifest] [key directory]`
+    Throughput(usize), // measure verification, `snarks throughput [ballots] [manifest]`
+    Inspect, // report on the circuit, `snarks inspect [manifest] [matrices file]`
+    Scaling, // time a vote by candidate count and curve, `snarks scaling [manifest]`
+    Compare, // compare Groth16, PLONK and bulletproofs on one vote, `snarks compare [manifest]`
+}
 
 // election simulation
 fn main() {
-    
-    // create voting system
-    let mut submission = VoteSubmission::new();
-    
-    println!("\n--- Testing Votes for Each Party ---");
-    
-    // array of possible votes
-    let parties = [
-        Party::Liberal,
-        Party::Conservative, 
-        Party::NDP,
-        Party::Green
+
+    // the subcommand comes first, the manifest path after it
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        Some("throughput") => {
+            args.remove(0);
+            match args.first().map(|count| count.parse::<usize>()) {
+                Some(Ok(count)) => {
+                    args.remove(0);
+                    Command::Throughput(count)
+                }
+                _ => Command::Throughput(10_000),
+            }
+        }
+        Some("inspect") => {
+            args.remove(0);
+            Command::Inspect
+        }
+        Some("scaling") => {
+            args.remove(0);
+            Command::Scaling
+        }
+        Some("compare") => {
+            args.remove(0);
+            Command::Compare
+        }
+        _ => Command::Simulate,
+    };
+
+    // load the election from its manifest, or from the path given on the command line
+    let path = args.first().cloned()
+        .unwrap_or_else(|| concat!(env!("CARGO_MANIFEST_DIR"), "/../elections/federal_election.toml").to_string());
+    let election = Election::load(&path)
+        .unwrap_or_else(|error| fail(&format!("Could not load {}", path), error.into()));
+    println!("Loaded {} ({}) with {} candidates on {}.", election.title, election.id, election.num_candidates(), election.curve);
+
+    // the manifest chooses the pairing curve the keys and proofs are built on
+    let output = args.get(1).map(PathBuf::from);
+    match election.curve {
+        Curve::Bls12_381 => run::<Bls12_381>(election, command, &path, output),
+        Curve::Bn254 => run::<Bn254>(election, command, &path, output),
+        Curve::Bls12_377 => run::<Bls12_377>(election, command, &path, output),
+    }
+
+}
+
+// function to run a command on one pairing curve
+fn run<E: SnarkCurve>(election: Election, command: Command, manifest: &str, output: Option<PathBuf>) {
+    match command {
+        Command::Simulate => simulate::<E>(election, output.unwrap_or_else(|| std::env::temp_dir().join("snarks_keys"))),
+        Command::Throughput(ballots) => throughput::<E>(election, ballots),
+        Command::Inspect => {
+            let output = output.unwrap_or_else(|| std::env::temp_dir().join(format!("{}.r1cs", election.id)));
+            inspect_circuit::<E>(election, output)
+        }
+        Command::Scaling => scaling::<E>(election),
+        Command::Compare => compare::<E>(election, manifest),
+    }
+}
+
+// function to report how the circuit and proving time grow with the candidate count and compare the curves
+fn scaling<E: SnarkCurve>(election: Election) {
+
+    println!("\n--- Proving Cost ---");
+
+    // the candidate check adds one constraint per candidate on top of the hashing, every size runs its own setup
+    println!("\nCircuit size and proving time by number of candidates:");
+    for num_candidates in [2, 4, 8, 12, 16] {
+        match vote_proof::proving_cost::<E>(num_candidates) {
+            Some((constraints, cost)) => println!("- {} candidates: {} constraints, {:.2} ms", num_candidates, constraints, cost.gen_time as f64 / 1_000_000.0),
+            None => println!("- {} candidates: could not be measured", num_candidates),
+        }
+    }
+
+    // the same circuit on every supported curve, the election runs on the one its manifest names
+    let num_candidates = election.num_candidates();
+    println!("\nProof size, proving and verification time by curve for {} candidates:", num_candidates);
+    let costs = [
+        (Curve::Bls12_381, vote_proof::proving_cost::<Bls12_381>(num_candidates)),
+        (Curve::Bn254, vote_proof::proving_cost::<Bn254>(num_candidates)),
+        (Curve::Bls12_377, vote_proof::proving_cost::<Bls12_377>(num_candidates)),
     ];
+    for (curve, cost) in costs {
+        match cost {
+            Some((_, cost)) => println!("- {}: {} bytes, {:.2} ms to prove, {:.2} ms to verify", curve, cost.proof_size,
+                cost.gen_time as f64 / 1_000_000.0, cost.verify_time as f64 / 1_000_000.0),
+            None => println!("- {}: could not be measured", curve),
+        }
+    }
+
+}
+
+// function to report what the voting circuit compiles to for this election
+fn inspect_circuit<E: SnarkCurve>(election: Election, output: PathBuf) {
+
+    println!("\n--- Circuit Inspection ---");
+
+    // synthesize the circuit with an honest vote for the first candidate
+    let (cs, report) = circuit::sample_circuit::<E::ScalarField>(&election, 0)
+        .and_then(inspect::inspect)
+        .unwrap_or_else(|error| fail("Synthesizing the circuit failed", error));
+    println!("\nConstraints: {}", report.constraints);
+    println!("Witness variables: {}", report.witness_variables);
+    println!("Public inputs: {}", report.public_inputs);
+    for (name, size) in &report.sections {
+        println!("- {}: {} constraints", name, size);
+    }
+    match report.unsatisfied {
+        None => println!("An honest vote satisfies every constraint."),
+        Some(constraint) => println!("An honest vote breaks {}.", constraint),
+    }
+
+    // export the matrices for external tools
+    match inspect::export_matrices(&cs, &output) {
+        Ok(()) => println!("R1CS matrices written to {}.", output.display()),
+        Err(error) => println!("The R1CS matrices could not be written: {}.", error),
+    }
+
+    // a vote outside the candidate list shows how a broken witness is reported
+    let outside = election.num_candidates() as u64;
+    match circuit::sample_circuit::<E::ScalarField>(&election, outside).and_then(inspect::inspect) {
+        Ok((_, report)) => match report.unsatisfied {
+            Some(constraint) => println!("\nA vote for candidate {} breaks {}.", outside, constraint),
+            None => println!("\nA vote for candidate {} satisfies every constraint.", outside),
+        },
+        Err(error) => println!("\nThe circuit for candidate {} could not be synthesized: {}.", outside, error),
+    }
+
+}
+
+// function to measure how many ballots per second the verifier processes
+fn throughput<E: SnarkCurve>(election: Election, ballots: usize) {
+
+    println!("\n--- Verification Throughput ---");
+
+    // register a few voters, proving thousands of distinct ballots would dominate the run
+    let voters: Vec<Voter> = (0..8).map(|i| Voter::new(format!("voter_{}", i))).collect();
+    let mut authority = RegistrationAuthority::<E::ScalarField>::new();
+    for voter in &voters {
+        authority.register(voter).unwrap_or_else(|error| fail("Voter registration failed", error));
+    }
+
+    // keys from a one-contribution ceremony verify exactly like the full ceremony's
+    let mut submission = ceremony::domain_size::<E>(&election)
+        .and_then(|size| {
+            let mut phase_one = PowersOfTau::<E>::new(size);
+            phase_one.contribute(&mut thread_rng()).map(|_| phase_one)
+        })
+        .and_then(|phase_one| Ceremony::<E>::new(&election, phase_one))
+        .and_then(|mut ceremony| ceremony.contribute(&mut thread_rng()).map(|_| ceremony))
+        .and_then(|ceremony| VoteSubmission::from_ceremony(election, authority.root(), ceremony))
+        .unwrap_or_else(|error| fail("Ceremony setup failed", error));
+    let num_candidates = submission.election().num_candidates();
+    let votes: Vec<SnarkVote<E>> = voters.iter().enumerate()
+        .map(|(i, voter)| authority.path(i)
+            .and_then(|path| submission.cast(voter, i % num_candidates, &path))
+            .unwrap_or_else(|error| fail("Proving a ballot failed", error)).0)
+        .collect();
+
+    // every path verifies the same ballots in turn until the count is reached
+    println!("\nVerifying {} ballots, cycling through {} distinct proofs...", ballots, votes.len());
+    match vote_proof::verification_throughput(&submission.ballot_box.params, submission.election(), &votes, ballots) {
+        Ok(rates) => {
+            println!("Verifying key prepared per ballot: {:.0} ballots/s", rates.unprepared);
+            println!("Prepared verifying key: {:.0} ballots/s ({:.2}x)", rates.prepared, rates.prepared / rates.unprepared);
+            println!("Prepared verifying key on {} threads: {:.0} ballots/s ({:.2}x)", pool_threads(), rates.pool, rates.pool / rates.unprepared);
+        }
+        Err(error) => println!("Throughput could not be measured: {}.", error),
+    }
+
+    // the distinct ballots are then accepted through the pool
+    let accepted = submission.submit_parallel(votes).into_iter().filter(|result| result.is_ok()).count();
+    println!("\n{} ballots accepted by the verification pool.", accepted);
+
+}
+
+// function to return the number of threads verification is spread over
+fn pool_threads() -> usize {
+    #[cfg(feature = "parallel")]
+    return rayon::current_num_threads();
+    #[cfg(not(feature = "parallel"))]
+    return 1;
+}
+
+// function to run the election on one pairing curve
+fn simulate<E: SnarkCurve>(election: Election, key_dir: PathBuf) {
+
+    let parties = election.candidates.clone();
+
+    // create every eligible voter
+    let voters: Vec<Voter> = (0..parties.len()).map(|i| Voter::new(format!("voter_{}", i))).collect();
+    let double_voter = Voter::new("double_voter".to_string());
+    let batch_voters: Vec<Voter> = (0..parties.len()).map(|i| Voter::new(format!("batch_voter_{}", i))).collect();
+
+    // register the eligible voters and publish the registry root
+    let mut authority = RegistrationAuthority::<E::ScalarField>::new();
+    let mut register = |voter: &Voter| authority.register(voter).unwrap_or_else(|error| fail("Voter registration failed", error));
+    let indices: Vec<usize> = voters.iter().map(&mut register).collect();
+    let double_index = register(&double_voter);
+    let batch_indices: Vec<usize> = batch_voters.iter().map(&mut register).collect();
+
+    // each voter fetches their authentication path once registration has closed
+    let path = |index: usize| authority.path(index).unwrap_or_else(|error| fail("Registered voter has no path", error));
+    let paths: Vec<_> = indices.into_iter().map(path).collect();
+    let double_path = path(double_index);
+    let batch_paths: Vec<_> = batch_indices.into_iter().map(path).collect();
+
+    println!("\n--- Trusted Setup Ceremony ---");
+
+    // phase one: each participant checks the powers of tau so far and multiplies tau, alpha and beta by their own secrets
+    let participants = 3;
+    let size = ceremony::domain_size::<E>(&election).unwrap_or_else(|error| fail("Ceremony setup failed", error));
+    let mut phase_one = PowersOfTau::<E>::new(size);
+    for round in 1..=participants {
+        match phase_one.check_consistency().and_then(|_| phase_one.contribute(&mut thread_rng())) {
+            Ok(()) => println!("\nParticipant {} contributed {} powers of tau.", round, size),
+            Err(error) => fail(&format!("Participant {} could not contribute", round), error),
+        }
+    }
+
+    // the coordinator derives the initial parameters for this election's candidate set from phase one and publishes their digest
+    std::fs::create_dir_all(&key_dir).unwrap_or_else(|error| fail("Creating key directory failed", VotingError::KeyStorage(error.to_string())));
+    let transcript = |round: usize| key_dir.join(format!("{}.ceremony.{}", election.id, round));
+    let pinned = Ceremony::<E>::new(&election, phase_one.clone())
+        .and_then(|ceremony| ceremony.save(&transcript(0)).and_then(|_| ceremony.initial_digest()))
+        .unwrap_or_else(|error| fail("Ceremony setup failed", error));
+    let hex: String = pinned.iter().map(|byte| format!("{:02x}", byte)).collect();
+    println!("\nInitial parameters published with digest {}.", hex);
+
+    // phase two: each participant checks the transcript so far, adds their randomness and passes the file on
+    for round in 1..=participants {
+        match Ceremony::<E>::load(&transcript(round - 1))
+            .and_then(|mut ceremony| ceremony.verify(&pinned).and_then(|_| ceremony.contribute(&mut thread_rng())).map(|_| ceremony))
+            .and_then(|ceremony| ceremony.save(&transcript(round))) {
+            Ok(()) => println!("\nParticipant {} contributed to phase two.", round),
+            Err(error) => fail(&format!("Participant {} could not contribute", round), error),
+        }
+    }
+
+    // a dishonest participant replaces delta with a value they know, without a valid proof
+    let mut forged = Ceremony::<E>::load(&transcript(participants)).unwrap_or_else(|error| fail("Loading transcript failed", error));
+    forged.contribute(&mut thread_rng()).unwrap_or_else(|error| fail("Forged contribution failed", error));
+    let known = E::ScalarField::from(42u64);
+    forged.current.delta_g1 = (E::G1Affine::generator() * known).into_affine();
+    forged.current.vk.delta_g2 = (E::G2Affine::generator() * known).into_affine();
+    if let Some(contribution) = forged.contributions.last_mut() {
+        contribution.delta_g1 = forged.current.delta_g1;
+    }
+    match forged.verify(&pinned) {
+        Ok(()) => println!("\nForged contribution accepted."),
+        Err(error) => println!("\nForged contribution rejected: {}.", error),
+    }
+
+    // the keys are only accepted once the honest transcript verifies
+    let mut submission = Ceremony::<E>::load(&transcript(participants))
+        .and_then(|ceremony| VoteSubmission::from_ceremony(election, authority.root(), ceremony))
+        .unwrap_or_else(|error| fail("Ballot box setup failed", error));
+    println!("\nSetup transcript with {} contributions verified.", participants);
+
+    println!("\n\n--- Testing Key Files ---");
+
+    // save the keys so provers and verifiers in other processes can share them, publishing the fingerprint
+    let fingerprint = submission.save_keys(&key_dir).unwrap_or_else(|error| fail("Saving keys failed", error));
+    let hex: String = fingerprint.iter().map(|byte| format!("{:02x}", byte)).collect();
+    println!("\nKeys saved to {} with fingerprint {}.", key_dir.display(), hex);
+
+    // a verifier loads the keys from the files and checks a ballot proven with the original keys
+    let mut verifier = VoteSubmission::from_keys(submission.election().clone(), authority.root(), &key_dir, &fingerprint)
+        .unwrap_or_else(|error| fail("Loading keys failed", error));
+    match submission.cast(&voters[0], 0, &paths[0]).and_then(|(vote, _)| verifier.submit_vote(vote)) {
+        Ok(()) => println!("Ballot verified with the keys loaded from file."),
+        Err(error) => println!("Ballot rejected with the keys loaded from file: {}.", error),
+    }
+
+    // keys that do not match the published fingerprint are refused
+    let mut forged = fingerprint;
+    forged[0] ^= 1;
+    match VoteSubmission::<E>::from_keys(submission.election().clone(), authority.root(), &key_dir, &forged) {
+        Ok(_) => println!("Keys accepted under a different fingerprint."),
+        Err(error) => println!("Keys refused under a different fingerprint: {}.", error),
+    }
+
+    // openings of the accepted votes, handed to the tallier once voting closes
+    let mut openings = Vec::new();
+    
+    println!("\n\n--- Testing Votes for Each Party ---");
 
     // create one vote for each party
-    for (i, party) in parties.iter().enumerate() {
-        
-        // create a new voter
-        let voter = Voter::new(format!("voter_{}", i));
+    for (((candidate, party), voter), path) in parties.iter().enumerate().zip(&voters).zip(&paths) {
         
-        println!("\nSubmitting vote for {:?}...", party);
+        println!("\nSubmitting vote for {}...", party);
         
-        // create a zk-SNARK proof
-        let vote = SnarkVote::new(*party, voter.nullifier(), &submission.pk);
-        
-        // submit the vote
-        if submission.submit_vote(vote) {
-            println!("Vote for {:?} accepted.", party);
-        } else {
-            println!("Vote for {:?} rejected.", party);
+        // create a zk-SNARK proof and submit the vote
+        match submission.cast(voter, candidate, path).and_then(|(vote, opening)| submission.submit_vote(vote).map(|_| opening)) {
+            Ok(opening) => {
+                openings.push(opening);
+                println!("Vote for {} accepted.", party);
+            }
+            Err(error) => println!("Vote for {} rejected: {}.", party, error),
         }
     }
 
     println!("\n\n--- Testing Double Voting Error Guarding ---");
     
-    // create a new voter that will try to vote twice
-    let double_voter = Voter::new("double_voter".to_string());
-    let nullifier = double_voter.nullifier();
-    
-    // duplicate voter testers first vote
-    println!("\nFirst Vote: Voting for Liberal...");
-    let vote1 = SnarkVote::new(Party::Liberal, nullifier, &submission.pk);
-    if submission.submit_vote(vote1) {
-        println!("Vote accepted.");
-    } else {
-        println!("Vote rejected.");
+    // duplicate voter testers first vote, for the first candidate and then the next one
+    let (first, second) = (0, 1 % parties.len());
+    println!("\nFirst Vote: Voting for {}...", parties[first]);
+    match submission.cast(&double_voter, first, &double_path).and_then(|(vote, opening)| submission.submit_vote(vote).map(|_| opening)) {
+        Ok(opening) => {
+            openings.push(opening);
+            println!("Vote accepted.");
+        }
+        Err(error) => println!("Vote rejected: {}.", error),
+    }
+
+    // duplicate voter testers second vote, re-created from their exported key
+    let recreated_voter = Voter::from_key("double_voter".to_string(), double_voter.export_key());
+    println!("\nSecond Vote (Duplicate Voter {} re-created from exported key): Voting for {}...", recreated_voter.id, parties[second]);
+    match submission.cast(&recreated_voter, second, &double_path).and_then(|(vote, opening)| submission.submit_vote(vote).map(|_| opening)) {
+        Ok(opening) => {
+            openings.push(opening);
+            println!("Vote accepted.");
+        }
+        Err(VotingError::DuplicateNullifier) => println!("Vote rejected, duplicate voter identified."),
+        Err(error) => println!("Vote rejected: {}.", error),
+    }
+
+    println!("\n\n--- Testing Ballot From Another Election ---");
+
+    // a ballot proven for a different election carries a different nullifier and election input
+    let candidate = 2 % parties.len();
+    println!("\nReplaying a provincial election ballot for {}...", parties[candidate]);
+    let provincial = Election::new("provincial_election".to_string(), parties.clone(), Vec::new(), 8)
+        .unwrap_or_else(|error| fail("Election definition failed", error.into()));
+    match SnarkVote::new(candidate, &double_voter, &double_path, &provincial, &submission.ballot_box.params.pk).and_then(|(vote, opening)| submission.submit_vote(vote).map(|_| opening)) {
+        Ok(opening) => {
+            openings.push(opening);
+            println!("Vote accepted.");
+        }
+        Err(VotingError::InvalidProof) => println!("Vote rejected, ballot was proven for another election."),
+        Err(error) => println!("Vote rejected: {}.", error),
+    }
+
+    println!("\n\n--- Testing Unregistered Voter ---");
+
+    // an unregistered voter can only build a path to a registry of their own
+    let outsider = Voter::new("unregistered_voter".to_string());
+    let fake_path = MerkleTree::<E::ScalarField>::new(&[outsider.leaf()]).ok_or(VotingError::RegistryFull)
+        .and_then(|fake_registry| fake_registry.path(0).ok_or(VotingError::NotRegistered))
+        .unwrap_or_else(|error| fail("Building a registry of their own failed", error));
+    let candidate = parties.len() - 1;
+    println!("\nUnregistered voter voting for {}...", parties[candidate]);
+    match submission.cast(&outsider, candidate, &fake_path).and_then(|(vote, opening)| submission.submit_vote(vote).map(|_| opening)) {
+        Ok(opening) => {
+            openings.push(opening);
+            println!("Vote accepted.");
+        }
+        Err(VotingError::InvalidProof) => println!("Vote rejected, voter is not in the registry."),
+        Err(error) => println!("Vote rejected: {}.", error),
+    }
+
+    println!("\n\n--- Testing Batch Submission ---");
+
+    // create a batch of votes from registered voters
+    let (mut batch, batch_openings): (Vec<SnarkVote<E>>, Vec<_>) = batch_voters.iter().zip(&batch_paths).enumerate()
+        .filter_map(|(candidate, (voter, path))| submission.cast(voter, candidate, path)
+            .map_err(|error| println!("Batch vote for {} failed: {}.", parties[candidate], error))
+            .ok())
+        .unzip();
+
+    // move one proof onto a different nullifier so the batch has to find it
+    if let Some(vote) = batch.last_mut() {
+        vote.nullifier = Voter::new("replayed_voter".to_string()).nullifier(submission.election().field_id());
+    }
+
+    // submit the batch and report each ballot
+    println!("\nSubmitting a batch of {} votes...", batch.len());
+    for (i, (result, opening)) in submission.submit_batch(batch).into_iter().zip(batch_openings).enumerate() {
+        match result {
+            Ok(()) => {
+                openings.push(opening);
+                println!("Batch vote {} accepted.", i);
+            }
+            Err(VotingError::InvalidProof) => println!("Batch vote {} rejected, invalid ballot identified.", i),
+            Err(error) => println!("Batch vote {} rejected: {}.", i, error),
+        }
+    }
+
+    println!("\n\n--- Auditing the Ballot Board ---");
+
+    // the aggregation key takes a from the election's phase one and b from a second transcript run by other participants
+    let ballots = submission.ballot_box.votes().len();
+    let mut second = PowersOfTau::<E>::new(AggregationKey::<E>::transcript_size(ballots));
+    for round in 1..=participants {
+        second.check_consistency().and_then(|_| second.contribute(&mut thread_rng()))
+            .unwrap_or_else(|error| fail(&format!("Participant {} could not contribute", round), error));
     }
 
-    // duplicate voter testers second vote
-    println!("\nSecond Vote (Duplicate Voter): Voting for Conservative...");
-    let vote2 = SnarkVote::new(Party::Conservative, nullifier, &submission.pk);
-    if submission.submit_vote(vote2) {
-        println!("Vote accepted.");
-    } else {
-        println!("Vote rejected, duplicate voter identified.");
+    // the board publishes one aggregate proof for every accepted ballot
+    match AggregationKey::from_powers_of_tau(&phase_one, &second, ballots)
+        .and_then(|aggregation_key| submission.aggregate(&aggregation_key).map(|aggregate| (aggregation_key, aggregate))) {
+        Ok((aggregation_key, aggregate)) => {
+            println!("\nAggregated the proofs of {} ballots.", submission.ballot_box.votes().len());
+
+            // an aggregate with a replaced proof element is caught
+            let mut forged = aggregate.clone();
+            forged.c = (forged.c + E::G1Affine::generator()).into_affine();
+            match submission.verify_aggregate(&aggregation_key, &forged) {
+                Ok(()) => println!("Forged aggregate proof accepted."),
+                Err(error) => println!("Forged aggregate proof rejected: {}.", error),
+            }
+
+            // an auditor checks the whole board with one verify call
+            match submission.verify_aggregate(&aggregation_key, &aggregate) {
+                Ok(()) => println!("Aggregate proof verified."),
+                Err(error) => println!("Aggregate proof rejected: {}.", error),
+            }
+        }
+        Err(error) => println!("\nThe ballot proofs could not be aggregated: {}.", error),
     }
 
     println!("\n\n--- Election Results ---");
     
-    // count votes for each party
-    let results = submission.tally();
-    
-    // display election results
-    for (party_id, count) in results {
-        let party_name = Party::from_u64(party_id);
-        println!("- {:?}: {} votes", party_name, count);
+    // count votes for each party by opening the accepted commitments
+    match submission.tally(&openings) {
+        Ok(tally) => tally.display(submission.election()),
+        Err(error) => println!("The tally could not be counted: {}.", error),
     }
 
     // display performance results
     submission.display_metrics();
     
-}
\ No newline at end of file
+}
+
+// function to prove and verify the same vote with Groth16 and PLONK, then run the bulletproofs binary on the same manifest
+fn compare<E: SnarkCurve>(election: Election, manifest: &str) {
+
+    println!("\n--- Backend Comparison ---");
+
+    // an honest vote for the first candidate, proven by both SNARKs
+    let circuit = circuit::sample_circuit::<E::ScalarField>(&election, 0)
+        .unwrap_or_else(|error| fail("Synthesizing the circuit failed", error));
+    let inputs = [circuit.nullifier_hash, circuit.commitment, circuit.root, circuit.election_id].map(Option::unwrap_or_default);
+    let root = inputs[2];
+    let millis = |start: Instant| start.elapsed().as_nanos() as f64 / 1_000_000.0;
+
+    // one powers-of-tau transcript large enough for both: Groth16 specializes it per circuit, PLONK indexes against it directly
+    let groth16_size = ceremony::domain_size::<E>(&election).unwrap_or_else(|error| fail("Ceremony setup failed", error));
+    let plonk_size = plonk::size(circuit.clone()).unwrap_or_else(|error| fail("Compiling the circuit failed", error));
+    let mut phase_one = PowersOfTau::<E>::new(groth16_size.max(plonk_size / 2 + 2));
+    phase_one.contribute(&mut thread_rng()).unwrap_or_else(|error| fail("Phase one failed", error));
+    println!("\nUniversal setup with {} powers of tau, circuit of {} Groth16 constraints and {} PLONK gates.", phase_one.size(), groth16_size, plonk_size);
+
+    // Groth16 needs a second, circuit-specific ceremony on top of phase one
+    let start = Instant::now();
+    let params = Ceremony::<E>::new(&election, phase_one.clone())
+        .and_then(|mut ceremony| ceremony.contribute(&mut thread_rng()).map(|_| ceremony))
+        .and_then(|ceremony| ceremony.finish(&election, root))
+        .unwrap_or_else(|error| fail("Ceremony setup failed", error));
+    let setup = millis(start);
+    let start = Instant::now();
+    let proof = Groth16::<E>::prove(&params.pk, circuit.clone(), &mut thread_rng())
+        .unwrap_or_else(|error| fail("Groth16 proving failed", VotingError::ProvingFailed(error.to_string())));
+    let proved = millis(start);
+    let start = Instant::now();
+    let verified = Groth16::<E>::verify_with_processed_vk(&params.pvk, &inputs, &proof).unwrap_or(false);
+    let verify = millis(start);
+    println!("\nGroth16: {} bytes, keys in {:.2} ms, proved in {:.2} ms, verified in {:.2} ms{}", proof.compressed_size(),
+        setup, proved, verify, if verified { "" } else { " (rejected)" });
+
+    // PLONK derives its keys from the universal setup without any further ceremony
+    let start = Instant::now();
+    let pk = plonk::index(&phase_one, circuit.clone()).unwrap_or_else(|error| fail("Indexing the circuit failed", error));
+    let setup = millis(start);
+    let start = Instant::now();
+    let proof = plonk::prove(&pk, circuit, &mut thread_rng()).unwrap_or_else(|error| fail("PLONK proving failed", error));
+    let proved = millis(start);
+    let start = Instant::now();
+    let verified = plonk::verify(&pk.vk, &inputs, &proof).is_ok();
+    let verify = millis(start);
+    println!("PLONK: {} bytes, keys in {:.2} ms, proved in {:.2} ms, verified in {:.2} ms{}", proof.compressed_size(),
+        setup, proved, verify, if verified { "" } else { " (rejected)" });
+
+    // the bulletproofs binary is built next to this one by `cargo build --release`
+    let binary = std::env::current_exe().map(|path| path.with_file_name(format!("bulletproofs{}", std::env::consts::EXE_SUFFIX)));
+    match binary.and_then(|binary| std::process::Command::new(binary).args(["metrics", manifest]).output()) {
+        Ok(output) if output.status.success() => print!("{}", String::from_utf8_lossy(&output.stdout)),
+        _ => println!("Bulletproofs: not measured, build both binaries with `cargo build --release` first."),
+    }
+
+}
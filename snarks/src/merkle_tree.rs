@@ -0,0 +1,91 @@
+// import types from libaries and modules
+use crate::curve::CircuitField;
+use crate::poseidon;
+
+// depth of the registry tree, allowing 2^TREE_DEPTH registered voters
+pub const TREE_DEPTH: usize = 8;
+
+// merkle tree structure
+#[derive(Debug, Clone)]
+pub struct MerkleTree<F: CircuitField> {
+    layers: Vec<Vec<F>>, // every level of the tree, leaves first
+}
+
+// authentication path from a leaf to the root
+#[derive(Debug, Clone)]
+pub struct MerklePath<F: CircuitField> {
+    pub siblings: Vec<F>, // sibling at each level, leaf level first
+    pub directions: Vec<bool>, // true when the running node is the right child
+}
+
+// merkle tree implementation
+impl<F: CircuitField> MerkleTree<F> {
+
+    // function to build a tree over the leaves, padding empty slots with zero
+    pub fn new(leaves: &[F]) -> Option<Self> {
+
+        // the registry is full
+        if leaves.len() > 1 << TREE_DEPTH {
+            return None;
+        }
+
+        // hash each level pairwise up to the root
+        let mut level = leaves.to_vec();
+        level.resize(1 << TREE_DEPTH, F::zero());
+        let mut layers = vec![level];
+        for _ in 0..TREE_DEPTH {
+            let next = layers[layers.len() - 1]
+                .chunks(2)
+                .map(|pair| poseidon::hash(&[pair[0], pair[1]]))
+                .collect();
+            layers.push(next);
+        }
+
+        Some(Self { layers })
+
+    }
+
+    // function to return the published root
+    pub fn root(&self) -> F {
+        self.layers[TREE_DEPTH][0]
+    }
+
+    // function to return the authentication path for a leaf
+    pub fn path(&self, index: usize) -> Option<MerklePath<F>> {
+
+        // index must point inside the tree
+        if index >= 1 << TREE_DEPTH {
+            return None;
+        }
+
+        // collect the sibling at each level
+        let mut siblings = Vec::with_capacity(TREE_DEPTH);
+        let mut directions = Vec::with_capacity(TREE_DEPTH);
+        let mut position = index;
+        for layer in &self.layers[..TREE_DEPTH] {
+            siblings.push(layer[position ^ 1]);
+            directions.push(position & 1 == 1);
+            position >>= 1;
+        }
+
+        Some(MerklePath { siblings, directions })
+
+    }
+
+}
+
+// merkle path implementation
+impl<F: CircuitField> MerklePath<F> {
+
+    // function to recompute the root a leaf hashes up to along this path
+    pub fn root(&self, leaf: F) -> F {
+        self.siblings.iter().zip(&self.directions).fold(leaf, |node, (sibling, &right)| {
+            if right {
+                poseidon::hash(&[*sibling, node])
+            } else {
+                poseidon::hash(&[node, *sibling])
+            }
+        })
+    }
+
+}
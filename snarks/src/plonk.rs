@@ -0,0 +1,519 @@
+// import types from libaries and modules
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{batch_inversion, FftField, Field, One, PrimeField, Zero};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, OptimizationGoal, SynthesisMode};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::{CryptoRng, RngCore}, UniformRand};
+use sha3::{Digest, Sha3_256};
+use voting_core::error::VotingError;
+use crate::powers_of_tau::{powers, PowersOfTau};
+
+// evaluation domain over the scalar field
+type Domain<F> = GeneralEvaluationDomain<F>;
+
+// polynomials opened at zeta: three wires, three permutations, five selectors, the accumulator and three quotient parts
+const OPENED: usize = 15;
+
+// public part of a circuit's index, committed with the universal setup
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlonkVerifyingKey<E: Pairing> {
+    pub size: usize, // number of gates, a power of two
+    pub num_inputs: usize, // public inputs, without the constant one
+    pub selectors: [E::G1Affine; 5], // commitments to q_l, q_r, q_o, q_m and q_c
+    pub sigmas: [E::G1Affine; 3], // commitments to the copy permutation of each wire
+    pub tau_g2: E::G2Affine, // tau in G2 from the universal setup
+}
+
+// everything the prover needs to prove a circuit, derived from the universal setup without any secret
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlonkProvingKey<E: Pairing> {
+    pub vk: PlonkVerifyingKey<E>, // verifying key
+    pub powers: Vec<E::G1Affine>, // tau^i in G1 for i < n + 3, the commitment key
+    pub selectors: [Vec<E::ScalarField>; 5], // coefficients of the selector polynomials
+    pub sigmas: [Vec<E::ScalarField>; 3], // coefficients of the permutation polynomials
+    pub sigma_values: [Vec<E::ScalarField>; 3], // permutation label of each wire at each gate
+}
+
+// PLONK proof with every polynomial opened at zeta, so the verifier checks the gate identity on field elements
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlonkProof<E: Pairing> {
+    pub wires: [E::G1Affine; 3], // commitments to the a, b and c wire polynomials
+    pub z: E::G1Affine, // commitment to the permutation accumulator
+    pub quotient: [E::G1Affine; 3], // commitments to the low, middle and high parts of the quotient
+    pub evaluations: [E::ScalarField; OPENED], // every opened polynomial at zeta
+    pub z_omega: E::ScalarField, // accumulator at zeta times the domain generator
+    pub w_zeta: E::G1Affine, // KZG opening of every polynomial at zeta
+    pub w_zeta_omega: E::G1Affine, // KZG opening of the accumulator at zeta times the generator
+}
+
+// gates compiled from the R1CS of a circuit, each enforcing q_l a + q_r b + q_o c + q_m a b + q_c + public input = 0
+struct Gates<F: PrimeField> {
+    selectors: [Vec<F>; 5], // q_l, q_r, q_o, q_m and q_c of each gate
+    wires: [Vec<usize>; 3], // variable on the a, b and c wire of each gate
+    values: Vec<F>, // value of each variable, empty when compiled without a witness
+    num_variables: usize, // R1CS variables and the sums the compiler adds
+}
+
+// gates implementation
+impl<F: PrimeField> Gates<F> {
+
+    // function to compile a finalized constraint system: the constant one, one gate per public input, then the constraints
+    fn compile(cs: &ConstraintSystemRef<F>) -> Result<Self, VotingError> {
+
+        let matrices = cs.to_matrices().ok_or_else(|| VotingError::SetupFailed("the constraint system keeps no matrices".to_string()))?;
+        let values = match cs.borrow() {
+            Some(system) if !cs.is_in_setup_mode() => [system.instance_assignment.as_slice(), system.witness_assignment.as_slice()].concat(),
+            _ => Vec::new(),
+        };
+        let mut gates = Self {
+            selectors: Default::default(),
+            wires: Default::default(),
+            values,
+            num_variables: matrices.num_instance_variables + matrices.num_witness_variables,
+        };
+
+        // variable 0 holds one, and each public input is read off the a wire of its own gate
+        let (zero, one) = (F::zero(), F::one());
+        gates.push([one, zero, zero, zero, -one], [0, 0, 0]);
+        for input in 1..matrices.num_instance_variables {
+            gates.push([one, zero, zero, zero, zero], [input, 0, 0]);
+        }
+
+        // each constraint reduces its three linear combinations to one variable each, then multiplies
+        for ((a, b), c) in matrices.a.iter().zip(&matrices.b).zip(&matrices.c) {
+            let (a_coefficient, a) = gates.reduce(a);
+            let (b_coefficient, b) = gates.reduce(b);
+            let (c_coefficient, c) = gates.reduce(c);
+            gates.push([zero, zero, -c_coefficient, a_coefficient * b_coefficient, zero], [a, b, c]);
+        }
+        Ok(gates)
+
+    }
+
+    // function to return the number of gates
+    fn len(&self) -> usize {
+        self.wires[0].len()
+    }
+
+    // function to append a gate
+    fn push(&mut self, selectors: [F; 5], wires: [usize; 3]) {
+        for (column, selector) in self.selectors.iter_mut().zip(selectors) {
+            column.push(selector);
+        }
+        for (column, variable) in self.wires.iter_mut().zip(wires) {
+            column.push(variable);
+        }
+    }
+
+    // function to fill the domain with gates that hold for any values, wired to the constant one
+    fn pad(&mut self, size: usize) {
+        while self.len() < size {
+            self.push([F::zero(); 5], [0, 0, 0]);
+        }
+    }
+
+    // function to return the value of a variable, zero without a witness
+    fn value(&self, variable: usize) -> F {
+        self.values.get(variable).copied().unwrap_or_else(F::zero)
+    }
+
+    // function to turn a linear combination into a coefficient times one variable, adding a gate per extra term
+    fn reduce(&mut self, combination: &[(F, usize)]) -> (F, usize) {
+        match combination {
+            [] => (F::zero(), 0),
+            [(coefficient, variable)] => (*coefficient, *variable),
+            [first, rest @ ..] => rest.iter().fold(*first, |sum, term| (F::one(), self.add(sum, *term))),
+        }
+    }
+
+    // function to add a variable holding x * left + y * right, with the gate that enforces it
+    fn add(&mut self, (x, left): (F, usize), (y, right): (F, usize)) -> usize {
+        let sum = self.num_variables;
+        self.num_variables += 1;
+        if !self.values.is_empty() {
+            self.values.push(x * self.value(left) + y * self.value(right));
+        }
+        self.push([x, y, -F::one(), F::zero(), F::zero()], [left, right, sum]);
+        sum
+    }
+
+}
+
+// function to synthesize a circuit keeping its linear combinations short, since every extra term costs an addition gate
+fn synthesize<F: PrimeField, C: ConstraintSynthesizer<F>>(circuit: C, mode: SynthesisMode) -> Result<ConstraintSystemRef<F>, VotingError> {
+    let cs = ConstraintSystem::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Weight);
+    cs.set_mode(mode);
+    circuit.generate_constraints(cs.clone()).map_err(|error| VotingError::SetupFailed(error.to_string()))?;
+    cs.finalize();
+    Ok(cs)
+}
+
+// function to return the number of gates a circuit compiles to, rounded up to a power of two
+pub fn size<F: PrimeField, C: ConstraintSynthesizer<F>>(circuit: C) -> Result<usize, VotingError> {
+    let gates = Gates::compile(&synthesize(circuit, SynthesisMode::Setup)?)?;
+    Domain::<F>::new(gates.len())
+        .map(|domain| domain.size())
+        .ok_or_else(|| VotingError::SetupFailed("the circuit is too large for the scalar field".to_string()))
+}
+
+// function to index a circuit against the universal setup, which needs no secret and no ceremony of its own
+pub fn index<E: Pairing, C: ConstraintSynthesizer<E::ScalarField>>(srs: &PowersOfTau<E>, circuit: C) -> Result<PlonkProvingKey<E>, VotingError> {
+
+    // the transcript is checked before any circuit is indexed against it
+    srs.verify()?;
+    let cs = synthesize(circuit, SynthesisMode::Setup)?;
+    let num_inputs = cs.num_instance_variables() - 1;
+    let mut gates = Gates::compile(&cs)?;
+    let domain = Domain::<E::ScalarField>::new(gates.len())
+        .ok_or_else(|| VotingError::SetupFailed("the circuit is too large for the scalar field".to_string()))?;
+    let size = domain.size();
+    gates.pad(size);
+
+    // the blinded accumulator and the quotient parts have n + 3 coefficients
+    if srs.tau_g1.len() < size + 3 {
+        return Err(VotingError::SetupFailed(format!("the circuit needs {} powers of tau, the setup has {}", size + 3, srs.tau_g1.len())));
+    }
+    let powers = srs.tau_g1[..size + 3].to_vec();
+
+    // selectors and permutation in coefficient form, committed for the verifier
+    let selectors = gates.selectors.each_ref().map(|values| domain.ifft(values));
+    let sigma_values = permutation(&gates, &domain);
+    let sigmas = sigma_values.each_ref().map(|values| domain.ifft(values));
+    let vk = PlonkVerifyingKey {
+        size,
+        num_inputs,
+        selectors: selectors.each_ref().map(|polynomial| msm(&powers[..polynomial.len()], polynomial)),
+        sigmas: sigmas.each_ref().map(|polynomial| msm(&powers[..polynomial.len()], polynomial)),
+        tau_g2: srs.tau_g2[1],
+    };
+    Ok(PlonkProvingKey { vk, powers, selectors, sigmas, sigma_values })
+
+}
+
+// function to label every wire with the next wire holding the same variable, so the copies of a variable form one cycle
+fn permutation<F: PrimeField>(gates: &Gates<F>, domain: &Domain<F>) -> [Vec<F>; 3] {
+
+    // wire i of gate j is labelled k_i * omega^j
+    let size = domain.size();
+    let elements: Vec<F> = domain.elements().collect();
+    let shifts = shifts::<F>();
+    let label = |slot: usize| shifts[slot / size] * elements[slot % size];
+
+    let mut slots = vec![Vec::new(); gates.num_variables];
+    for (column, wires) in gates.wires.iter().enumerate() {
+        for (row, variable) in wires.iter().enumerate() {
+            slots[*variable].push(column * size + row);
+        }
+    }
+    let mut sigmas: [Vec<F>; 3] = std::array::from_fn(|_| vec![F::zero(); size]);
+    for cycle in slots.iter().filter(|cycle| !cycle.is_empty()) {
+        for (i, slot) in cycle.iter().enumerate() {
+            sigmas[slot / size][slot % size] = label(cycle[(i + 1) % cycle.len()]);
+        }
+    }
+    sigmas
+
+}
+
+// function to return the coset shifts of the three wire columns, a generator of the multiplicative group and its square
+fn shifts<F: PrimeField>() -> [F; 3] {
+    [F::one(), F::GENERATOR, F::GENERATOR.square()]
+}
+
+// function to prove that the witness in a circuit satisfies the indexed constraints
+pub fn prove<E: Pairing, C: ConstraintSynthesizer<E::ScalarField>, R: RngCore + CryptoRng>(pk: &PlonkProvingKey<E>, circuit: C, rng: &mut R) -> Result<PlonkProof<E>, VotingError> {
+
+    let cs = synthesize(circuit, SynthesisMode::Prove { construct_matrices: true })?;
+    let inputs = cs.borrow().map(|system| system.instance_assignment[1..].to_vec()).unwrap_or_default();
+    let mut gates = Gates::compile(&cs)?;
+    let size = pk.vk.size;
+    if gates.len() > size || inputs.len() != pk.vk.num_inputs {
+        return Err(VotingError::ProvingFailed("the circuit does not match the proving key".to_string()));
+    }
+    gates.pad(size);
+    let domain = Domain::<E::ScalarField>::new(size)
+        .ok_or_else(|| VotingError::ProvingFailed("the circuit is too large for the scalar field".to_string()))?;
+    let omega = domain.group_gen();
+    let commit = |polynomial: &Vec<E::ScalarField>| msm(&pk.powers[..polynomial.len()], polynomial);
+
+    // round one: wire values, blinded by a random multiple of the vanishing polynomial
+    let values = gates.wires.each_ref().map(|wires| wires.iter().map(|variable| gates.value(*variable)).collect::<Vec<_>>());
+    let wires = values.each_ref().map(|values| blind(domain.ifft(values), &random(rng, 2), size));
+    let wire_commitments = wires.each_ref().map(commit);
+    let beta = challenge(seed(&pk.vk, &inputs)?, &wire_commitments)?;
+    let gamma = challenge(beta, &wire_commitments)?;
+
+    // round two: running product of the permutation ratio, which returns to one only if the copies agree
+    let shifts = shifts::<E::ScalarField>();
+    let elements: Vec<E::ScalarField> = domain.elements().collect();
+    let mut numerators = vec![E::ScalarField::one(); size];
+    let mut denominators = vec![E::ScalarField::one(); size];
+    for column in 0..3 {
+        for row in 0..size {
+            numerators[row] *= values[column][row] + beta * shifts[column] * elements[row] + gamma;
+            denominators[row] *= values[column][row] + beta * pk.sigma_values[column][row] + gamma;
+        }
+    }
+    batch_inversion(&mut denominators);
+    let mut accumulator = Vec::with_capacity(size);
+    let mut product = E::ScalarField::one();
+    for row in 0..size {
+        accumulator.push(product);
+        product *= numerators[row] * denominators[row];
+    }
+    if !product.is_one() {
+        return Err(VotingError::ProvingFailed("the witness breaks a copy constraint".to_string()));
+    }
+    let z = blind(domain.ifft(&accumulator), &random(rng, 3), size);
+    let z_commitment = commit(&z);
+    let alpha = challenge(gamma, &z_commitment)?;
+
+    // round three: quotient of the gate, permutation and boundary terms by the vanishing polynomial, on a coset eight times larger
+    let coset = Domain::<E::ScalarField>::new(8 * size)
+        .and_then(|domain| domain.get_coset(E::ScalarField::GENERATOR))
+        .ok_or_else(|| VotingError::ProvingFailed("the circuit is too large for the scalar field".to_string()))?;
+    let [a, b, c] = wires.each_ref().map(|polynomial| coset.fft(polynomial));
+    let q = pk.selectors.each_ref().map(|polynomial| coset.fft(polynomial));
+    let s = pk.sigmas.each_ref().map(|polynomial| coset.fft(polynomial));
+    let z_coset = coset.fft(&z);
+    let public = coset.fft(&domain.ifft(&public_values(&inputs, size)));
+    let mut first = vec![E::ScalarField::zero(); size];
+    first[0] = E::ScalarField::one();
+    let first = coset.fft(&domain.ifft(&first));
+    let points: Vec<E::ScalarField> = coset.elements().collect();
+    let mut vanishing: Vec<E::ScalarField> = points.iter().map(|x| x.pow([size as u64]) - E::ScalarField::one()).collect();
+    batch_inversion(&mut vanishing);
+    let quotient: Vec<E::ScalarField> = (0..points.len()).map(|i| {
+        let x = points[i];
+
+        // z at omega * x is eight points further round the coset
+        let z_next = z_coset[(i + 8) % points.len()];
+        let gate = q[0][i] * a[i] + q[1][i] * b[i] + q[2][i] * c[i] + q[3][i] * a[i] * b[i] + q[4][i] + public[i];
+        let permutation = (a[i] + beta * x + gamma) * (b[i] + beta * shifts[1] * x + gamma) * (c[i] + beta * shifts[2] * x + gamma) * z_coset[i]
+            - (a[i] + beta * s[0][i] + gamma) * (b[i] + beta * s[1][i] + gamma) * (c[i] + beta * s[2][i] + gamma) * z_next;
+        let boundary = (z_coset[i] - E::ScalarField::one()) * first[i];
+        (gate + alpha * permutation + alpha * alpha * boundary) * vanishing[i]
+    }).collect();
+    let t = coset.ifft(&quotient);
+
+    // a quotient above degree 3n + 5 means some gate does not hold
+    if t[3 * size + 6..].iter().any(|coefficient| !coefficient.is_zero()) {
+        return Err(VotingError::ProvingFailed("the witness does not satisfy the circuit".to_string()));
+    }
+
+    // three parts of n + 2 coefficients, each blinded by a term the next part takes back
+    let (b_low, b_middle) = (E::ScalarField::rand(rng), E::ScalarField::rand(rng));
+    let mut low = t[..size + 2].to_vec();
+    low.push(b_low);
+    let mut middle = t[size + 2..2 * size + 4].to_vec();
+    middle[0] -= b_low;
+    middle.push(b_middle);
+    let mut high = t[2 * size + 4..3 * size + 6].to_vec();
+    high[0] -= b_middle;
+    let parts = [low, middle, high];
+    let quotient_commitments = parts.each_ref().map(commit);
+    let zeta = challenge(alpha, &quotient_commitments)?;
+
+    // round four: open every polynomial at zeta and the accumulator at the next point
+    let opened: [&Vec<E::ScalarField>; OPENED] = [
+        &wires[0], &wires[1], &wires[2],
+        &pk.sigmas[0], &pk.sigmas[1], &pk.sigmas[2],
+        &pk.selectors[0], &pk.selectors[1], &pk.selectors[2], &pk.selectors[3], &pk.selectors[4],
+        &z, &parts[0], &parts[1], &parts[2],
+    ];
+    let evaluations = opened.map(|polynomial| evaluate(polynomial, zeta));
+    let z_omega = evaluate(&z, zeta * omega);
+    let v = challenge(zeta, &(evaluations, z_omega))?;
+
+    // round five: one KZG opening for the random combination at zeta and one for the accumulator
+    let mut combined = vec![E::ScalarField::zero(); size + 3];
+    for ((polynomial, evaluation), weight) in opened.iter().zip(evaluations).zip(powers(v, OPENED)) {
+        for (sum, coefficient) in combined.iter_mut().zip(polynomial.iter()) {
+            *sum += weight * coefficient;
+        }
+        combined[0] -= weight * evaluation;
+    }
+    let w_zeta = commit(&divide_by_linear(&combined, zeta));
+    let w_zeta_omega = commit(&divide_by_linear(&z, zeta * omega));
+
+    Ok(PlonkProof {
+        wires: wire_commitments,
+        z: z_commitment,
+        quotient: quotient_commitments,
+        evaluations,
+        z_omega,
+        w_zeta,
+        w_zeta_omega,
+    })
+
+}
+
+// function to check a proof against the verifying key and the public inputs
+pub fn verify<E: Pairing>(vk: &PlonkVerifyingKey<E>, inputs: &[E::ScalarField], proof: &PlonkProof<E>) -> Result<(), VotingError> {
+
+    if inputs.len() != vk.num_inputs {
+        return Err(VotingError::InvalidProof);
+    }
+    let domain = Domain::<E::ScalarField>::new(vk.size).ok_or(VotingError::InvalidProof)?;
+    let (size, omega, one) = (vk.size, domain.group_gen(), E::ScalarField::one());
+
+    // the challenges are recomputed from the commitments
+    let beta = challenge(seed(vk, inputs)?, &proof.wires)?;
+    let gamma = challenge(beta, &proof.wires)?;
+    let alpha = challenge(gamma, &proof.z)?;
+    let zeta = challenge(alpha, &proof.quotient)?;
+    let v = challenge(zeta, &(proof.evaluations, proof.z_omega))?;
+    let u = challenge(v, &(proof.w_zeta, proof.w_zeta_omega))?;
+
+    // lagrange polynomials of the constant and public input gates at zeta
+    let vanishing = zeta.pow([size as u64]) - one;
+    if vanishing.is_zero() {
+        return Err(VotingError::InvalidProof);
+    }
+    let n = E::ScalarField::from(size as u64);
+    let lagrange: Vec<E::ScalarField> = powers(omega, inputs.len() + 1).into_iter()
+        .map(|point| point * vanishing / (n * (zeta - point)))
+        .collect();
+    let public: E::ScalarField = inputs.iter().zip(&lagrange[1..]).map(|(input, basis)| -*input * basis).sum();
+
+    // the gate, permutation and boundary terms equal the quotient times the vanishing polynomial
+    let [a, b, c, s1, s2, s3, q_l, q_r, q_o, q_m, q_c, z, t_low, t_middle, t_high] = proof.evaluations;
+    let shifts = shifts::<E::ScalarField>();
+    let gate = q_l * a + q_r * b + q_o * c + q_m * a * b + q_c + public;
+    let permutation = (a + beta * zeta + gamma) * (b + beta * shifts[1] * zeta + gamma) * (c + beta * shifts[2] * zeta + gamma) * z
+        - (a + beta * s1 + gamma) * (b + beta * s2 + gamma) * (c + beta * s3 + gamma) * proof.z_omega;
+    let boundary = (z - one) * lagrange[0];
+    let t = t_low + zeta.pow([size as u64 + 2]) * t_middle + zeta.pow([2 * size as u64 + 4]) * t_high;
+    if gate + alpha * permutation + alpha * alpha * boundary != t * vanishing {
+        return Err(VotingError::InvalidProof);
+    }
+
+    // the evaluations are those of the committed polynomials: e(W + u W', tau) = e(zeta W + u zeta omega W' + F - E + u (Z - z_omega), 1)
+    let commitments = [
+        proof.wires[0], proof.wires[1], proof.wires[2],
+        vk.sigmas[0], vk.sigmas[1], vk.sigmas[2],
+        vk.selectors[0], vk.selectors[1], vk.selectors[2], vk.selectors[3], vk.selectors[4],
+        proof.z, proof.quotient[0], proof.quotient[1], proof.quotient[2],
+    ];
+    let weights = powers(v, OPENED);
+    let g1 = E::G1Affine::generator();
+    let combined = msm(&commitments, &weights).into_group();
+    let evaluation: E::ScalarField = proof.evaluations.iter().zip(&weights).map(|(evaluation, weight)| *evaluation * weight).sum();
+    let left = proof.w_zeta.into_group() + proof.w_zeta_omega * u;
+    let right = proof.w_zeta * zeta + proof.w_zeta_omega * (u * zeta * omega) + combined - g1 * evaluation
+        + (proof.z.into_group() - g1 * proof.z_omega) * u;
+    let check = E::multi_pairing([left.into_affine(), (-right).into_affine()], [vk.tau_g2, E::G2Affine::generator()]);
+    if check.is_zero() { Ok(()) } else { Err(VotingError::InvalidProof) }
+
+}
+
+// function to add a random multiple of X^n - 1, which leaves the values on the domain unchanged
+fn blind<F: PrimeField>(mut coefficients: Vec<F>, blinding: &[F], size: usize) -> Vec<F> {
+    coefficients.resize(size + blinding.len(), F::zero());
+    for (i, factor) in blinding.iter().enumerate() {
+        coefficients[i] -= factor;
+        coefficients[size + i] += factor;
+    }
+    coefficients
+}
+
+// function to return the public input polynomial's values, minus each input on its gate
+fn public_values<F: PrimeField>(inputs: &[F], size: usize) -> Vec<F> {
+    let mut values = vec![F::zero(); size];
+    for (value, input) in values[1..].iter_mut().zip(inputs) {
+        *value = -*input;
+    }
+    values
+}
+
+// function to draw blinding scalars
+fn random<F: PrimeField, R: RngCore + CryptoRng>(rng: &mut R, count: usize) -> Vec<F> {
+    (0..count).map(|_| F::rand(rng)).collect()
+}
+
+// function to start the transcript from the verifying key and the public inputs
+fn seed<E: Pairing>(vk: &PlonkVerifyingKey<E>, inputs: &[E::ScalarField]) -> Result<E::ScalarField, VotingError> {
+    challenge(challenge(E::ScalarField::zero(), vk)?, &inputs.to_vec())
+}
+
+// function to derive a non-zero challenge from the previous one and a message
+fn challenge<F: PrimeField, T: CanonicalSerialize>(previous: F, message: &T) -> Result<F, VotingError> {
+    let mut bytes = Vec::new();
+    previous.serialize_compressed(&mut bytes)
+        .and_then(|_| message.serialize_compressed(&mut bytes))
+        .map_err(|error| VotingError::Serialization(error.to_string()))?;
+    let mut counter = 0u64;
+    loop {
+        let mut hash = Sha3_256::new();
+        hash.update(b"plonk");
+        hash.update(counter.to_le_bytes());
+        hash.update(&bytes);
+        let challenge = F::from_le_bytes_mod_order(&hash.finalize());
+        if !challenge.is_zero() {
+            return Ok(challenge);
+        }
+        counter += 1;
+    }
+}
+
+// function to divide a polynomial by (X - z), dropping the remainder
+pub fn divide_by_linear<F: PrimeField>(coefficients: &[F], z: F) -> Vec<F> {
+    let mut quotient = vec![F::zero(); coefficients.len().saturating_sub(1)];
+    let mut carry = F::zero();
+    for i in (1..coefficients.len()).rev() {
+        carry = coefficients[i] + carry * z;
+        quotient[i - 1] = carry;
+    }
+    quotient
+}
+
+// function to evaluate a polynomial at a point
+pub fn evaluate<F: PrimeField>(coefficients: &[F], point: F) -> F {
+    coefficients.iter().rev().fold(F::zero(), |sum, coefficient| sum * point + coefficient)
+}
+
+// function to compute the inner product of points and scalars
+pub fn msm<A: AffineRepr>(points: &[A], scalars: &[A::ScalarField]) -> A {
+    A::Group::msm_unchecked(points, scalars).into_affine()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr as BlsFr};
+    use ark_std::rand::thread_rng;
+    use crate::circuit;
+    use crate::election::Election;
+
+    #[test]
+    fn vote_circuit_proves_against_the_universal_setup() {
+        let election = Election::new("election_1".to_string(), vec!["a".to_string(), "b".to_string()], Vec::new(), 8).unwrap();
+        let circuit = circuit::sample_circuit(&election, 1).unwrap();
+        let inputs = [circuit.nullifier_hash, circuit.commitment, circuit.root, circuit.election_id].map(Option::unwrap);
+
+        // the same powers of tau index the circuit once someone has contributed to them, no ceremony for it is needed
+        let size = size(circuit.clone()).unwrap();
+        let mut srs = PowersOfTau::<Bls12_381>::new(size / 2 + 2);
+        assert_eq!(index(&srs, circuit.clone()).err(), Some(VotingError::SetupFailed("phase one has no contributions".to_string())));
+        srs.contribute(&mut thread_rng()).unwrap();
+        let pk = index(&srs, circuit.clone()).unwrap();
+
+        // an honest vote verifies, with the same public inputs only
+        let proof = prove(&pk, circuit, &mut thread_rng()).unwrap();
+        assert_eq!(verify(&pk.vk, &inputs, &proof), Ok(()));
+        let mut other = inputs;
+        other[0] += BlsFr::one();
+        assert_eq!(verify(&pk.vk, &other, &proof), Err(VotingError::InvalidProof));
+
+        // an opened value that was changed after the fact is caught
+        let mut tampered = proof.clone();
+        tampered.evaluations[0] += BlsFr::one();
+        assert_eq!(verify(&pk.vk, &inputs, &tampered), Err(VotingError::InvalidProof));
+
+        // a vote for a candidate that is not on the ballot cannot be proven
+        let invalid = circuit::sample_circuit(&election, 2).unwrap();
+        assert!(prove(&pk, invalid, &mut thread_rng()).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+// import types from libaries and modules
+use ark_crypto_primitives::crh::{CRHScheme, CRHSchemeGadget};
+use ark_crypto_primitives::crh::poseidon::{CRH, constraints::{CRHGadget, CRHParametersVar}};
+use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_relations::r1cs::SynthesisError;
+use crate::curve::CircuitField;
+
+// domain tags so leaves, nullifiers and vote commitments never collide
+pub const LEAF_DOMAIN: u64 = 1;
+pub const NULLIFIER_DOMAIN: u64 = 2;
+pub const COMMITMENT_DOMAIN: u64 = 3;
+
+// poseidon parameters shared by every field (rate 2, capacity 1), the s-box and partial rounds are chosen per field
+const FULL_ROUNDS: usize = 8;
+const RATE: usize = 2;
+
+// function to generate poseidon parameters for a field
+pub fn parameters<F: PrimeField>(alpha: u64, partial_rounds: usize) -> PoseidonConfig<F> {
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(F::MODULUS_BIT_SIZE as u64, RATE, FULL_ROUNDS as u64, partial_rounds as u64, 0);
+    PoseidonConfig::new(FULL_ROUNDS, partial_rounds, alpha, mds, ark, RATE, 1)
+}
+
+// function to return the shared poseidon parameters of a field
+pub fn config<F: CircuitField>() -> &'static PoseidonConfig<F> {
+    F::poseidon_config()
+}
+
+// function to hash field elements outside the circuit
+pub fn hash<F: CircuitField>(inputs: &[F]) -> F {
+    CRH::<F>::evaluate(config(), inputs).expect("Poseidon hash failed.")
+}
+
+// function to hash field elements inside the circuit
+pub fn hash_gadget<F: CircuitField>(params: &CRHParametersVar<F>, inputs: &[FpVar<F>]) -> Result<FpVar<F>, SynthesisError> {
+    CRHGadget::<F>::evaluate(params, inputs)
+}
@@ -0,0 +1,282 @@
+// import types from libaries and modules
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::{thread_rng, CryptoRng, RngCore}, UniformRand};
+use sha3::{Digest, Sha3_256};
+use voting_core::error::VotingError;
+
+// proof that a participant knows the factor relating a point before and after their update
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct KnowledgeProof<E: Pairing> {
+    pub s: E::G1Affine, // random base chosen by the participant
+    pub s_x: E::G1Affine, // base multiplied by the participant's secret
+    pub r_x: E::G2Affine, // transcript challenge multiplied by the participant's secret
+}
+
+// one participant's update of tau, alpha and beta
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct TauContribution<E: Pairing> {
+    pub tau_g1: E::G1Affine, // tau in G1 after this contribution
+    pub alpha_g1: E::G1Affine, // alpha in G1 after this contribution
+    pub beta_g1: E::G1Affine, // beta in G1 after this contribution
+    pub tau_proof: KnowledgeProof<E>, // knowledge of the factor applied to tau
+    pub alpha_proof: KnowledgeProof<E>, // knowledge of the factor applied to alpha
+    pub beta_proof: KnowledgeProof<E>, // knowledge of the factor applied to beta
+}
+
+// phase-one transcript: powers of a secret tau, and of tau times secret alpha and beta, for any circuit up to its size
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PowersOfTau<E: Pairing> {
+    pub tau_g1: Vec<E::G1Affine>, // tau^i in G1 for i < 2n - 1
+    pub tau_g2: Vec<E::G2Affine>, // tau^i in G2 for i < n
+    pub alpha_tau_g1: Vec<E::G1Affine>, // alpha * tau^i in G1 for i < n
+    pub beta_tau_g1: Vec<E::G1Affine>, // beta * tau^i in G1 for i < n
+    pub beta_g2: E::G2Affine, // beta in G2
+    pub contributions: Vec<TauContribution<E>>, // every update in order
+}
+
+// powers of tau implementation
+impl<E: Pairing> PowersOfTau<E> {
+
+    // function to start from tau = alpha = beta = 1, which anyone can recompute, for circuits with up to n constraints and inputs
+    pub fn new(size: usize) -> Self {
+        let (g1, g2) = (E::G1Affine::generator(), E::G2Affine::generator());
+        Self {
+            tau_g1: vec![g1; 2 * size - 1],
+            tau_g2: vec![g2; size],
+            alpha_tau_g1: vec![g1; size],
+            beta_tau_g1: vec![g1; size],
+            beta_g2: g2,
+            contributions: Vec::new(),
+        }
+    }
+
+    // function to return n, the largest evaluation domain the transcript supports
+    pub fn size(&self) -> usize {
+        self.tau_g2.len()
+    }
+
+    // function to multiply tau, alpha and beta by fresh secrets and prove each update
+    pub fn contribute<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Result<(), VotingError> {
+
+        // the secrets are dropped once the update is applied
+        let (tau, alpha, beta) = (E::ScalarField::rand(rng), E::ScalarField::rand(rng), E::ScalarField::rand(rng));
+        if tau.is_zero() || alpha.is_zero() || beta.is_zero() {
+            return Err(VotingError::SetupFailed("zero contribution".to_string()));
+        }
+        let tau_proof = prove_knowledge::<E, R>(&self.tau_g1[1], tau, rng)?;
+        let alpha_proof = prove_knowledge::<E, R>(&self.alpha_tau_g1[0], alpha, rng)?;
+        let beta_proof = prove_knowledge::<E, R>(&self.beta_tau_g1[0], beta, rng)?;
+
+        // the i-th power is multiplied by tau^i, the alpha and beta powers by alpha or beta as well
+        let size = self.size();
+        let powers = powers(tau, self.tau_g1.len());
+        self.tau_g1 = scale_each(&self.tau_g1, &powers);
+        self.tau_g2 = scale_each(&self.tau_g2, &powers[..size]);
+        self.alpha_tau_g1 = scale_each(&self.alpha_tau_g1, &powers[..size].iter().map(|power| *power * alpha).collect::<Vec<_>>());
+        self.beta_tau_g1 = scale_each(&self.beta_tau_g1, &powers[..size].iter().map(|power| *power * beta).collect::<Vec<_>>());
+        self.beta_g2 = (self.beta_g2 * beta).into_affine();
+
+        self.contributions.push(TauContribution {
+            tau_g1: self.tau_g1[1],
+            alpha_g1: self.alpha_tau_g1[0],
+            beta_g1: self.beta_tau_g1[0],
+            tau_proof,
+            alpha_proof,
+            beta_proof,
+        });
+        Ok(())
+
+    }
+
+    // function to check the transcript has contributions, every one of them, and that the powers are consistent with the last one
+    pub fn verify(&self) -> Result<(), VotingError> {
+
+        // without a contribution tau, alpha and beta are still one, which everyone knows
+        if self.contributions.is_empty() {
+            return Err(VotingError::SetupFailed("phase one has no contributions".to_string()));
+        }
+        self.check_consistency()
+
+    }
+
+    // function to check every contribution and that the powers are consistent with the last one, or with one if there is none
+    pub fn check_consistency(&self) -> Result<(), VotingError> {
+
+        // the powers are blamed on the last participant
+        let last = VotingError::InvalidContribution(self.contributions.len());
+        let size = self.size();
+        let (g1, g2) = (E::G1Affine::generator(), E::G2Affine::generator());
+        if size < 2
+            || self.tau_g1.len() != 2 * size - 1
+            || self.alpha_tau_g1.len() != size
+            || self.beta_tau_g1.len() != size
+            || self.tau_g1[0] != g1
+            || self.tau_g2[0] != g2 {
+            return Err(last);
+        }
+
+        // each participant knows the factors they applied to tau, alpha and beta
+        let mut previous = (g1, g1, g1);
+        for (i, contribution) in self.contributions.iter().enumerate() {
+            if contribution.tau_g1.is_zero()
+                || contribution.alpha_g1.is_zero()
+                || contribution.beta_g1.is_zero()
+                || !verify_knowledge(&contribution.tau_proof, previous.0, contribution.tau_g1)?
+                || !verify_knowledge(&contribution.alpha_proof, previous.1, contribution.alpha_g1)?
+                || !verify_knowledge(&contribution.beta_proof, previous.2, contribution.beta_g1)? {
+                return Err(VotingError::InvalidContribution(i + 1));
+            }
+            previous = (contribution.tau_g1, contribution.alpha_g1, contribution.beta_g1);
+        }
+        if (self.tau_g1[1], self.alpha_tau_g1[0], self.beta_tau_g1[0]) != previous {
+            return Err(last);
+        }
+
+        // consecutive elements differ by tau, checked on random combinations of them
+        let tau_g2 = self.tau_g2[1];
+        for powers in [&self.tau_g1, &self.alpha_tau_g1, &self.beta_tau_g1] {
+            let (lower, upper) = consecutive::<E::G1Affine>(powers).ok_or_else(|| last.clone())?;
+            if !same_ratio::<E>((lower, upper), (g2, tau_g2)) {
+                return Err(last);
+            }
+        }
+        let (lower, upper) = consecutive::<E::G2Affine>(&self.tau_g2).ok_or_else(|| last.clone())?;
+        if !same_ratio::<E>((g1, self.tau_g1[1]), (lower, upper)) {
+            return Err(last);
+        }
+
+        // beta is the same in both groups
+        if !same_ratio::<E>((g1, self.beta_tau_g1[0]), (g2, self.beta_g2)) {
+            return Err(last);
+        }
+        Ok(())
+
+    }
+
+}
+
+// function to prove knowledge of the secret that moves a point, bound to the point it moves
+fn prove_knowledge<E: Pairing, R: RngCore + CryptoRng>(previous: &E::G1Affine, secret: E::ScalarField, rng: &mut R) -> Result<KnowledgeProof<E>, VotingError> {
+    let s = E::G1::rand(rng).into_affine();
+    let s_x = (s * secret).into_affine();
+    let r = challenge::<E>(b"powers-of-tau-contribution", previous, &s, &s_x)?;
+    Ok(KnowledgeProof { s, s_x, r_x: (r * secret).into_affine() })
+}
+
+// function to check a proof of knowledge, and that the same secret moved previous to next
+fn verify_knowledge<E: Pairing>(proof: &KnowledgeProof<E>, previous: E::G1Affine, next: E::G1Affine) -> Result<bool, VotingError> {
+    let r = challenge::<E>(b"powers-of-tau-contribution", &previous, &proof.s, &proof.s_x)?;
+    Ok(!proof.s.is_zero()
+        && same_ratio::<E>((proof.s, proof.s_x), (r, proof.r_x))
+        && same_ratio::<E>((previous, next), (r, proof.r_x)))
+}
+
+// function to combine every element but the last, and every element but the first, with the same random weights
+fn consecutive<G: AffineRepr>(powers: &[G]) -> Option<(G, G)> {
+    let mut rng = thread_rng();
+    let weights: Vec<G::ScalarField> = (1..powers.len()).map(|_| G::ScalarField::rand(&mut rng)).collect();
+    let lower = G::Group::msm(&powers[..powers.len() - 1], &weights).ok()?;
+    let upper = G::Group::msm(&powers[1..], &weights).ok()?;
+    Some((lower.into_affine(), upper.into_affine()))
+}
+
+// function to derive the challenge point for a contribution from the point it updates and its proof base
+pub fn challenge<E: Pairing>(label: &[u8], previous: &E::G1Affine, s: &E::G1Affine, s_x: &E::G1Affine) -> Result<E::G2Affine, VotingError> {
+    let mut transcript = Vec::new();
+    (*previous, *s, *s_x).serialize_compressed(&mut transcript).map_err(|error| VotingError::Serialization(error.to_string()))?;
+
+    // try-and-increment: hash the transcript and a counter until the bytes decode to a point, then move it into the prime order subgroup
+    let size = E::G2Affine::zero().compressed_size();
+    for counter in 0u64.. {
+        let mut bytes = Vec::with_capacity(size + 32);
+        for block in 0u64.. {
+            if bytes.len() >= size {
+                break;
+            }
+            let mut hash = Sha3_256::new();
+            hash.update(label);
+            hash.update(counter.to_le_bytes());
+            hash.update(block.to_le_bytes());
+            hash.update(&transcript);
+            bytes.extend_from_slice(&hash.finalize());
+        }
+        if let Some(point) = E::G2Affine::from_random_bytes(&bytes[..size]) {
+            let point = point.clear_cofactor();
+            if !point.is_zero() {
+                return Ok(point);
+            }
+        }
+    }
+    unreachable!("the counter does not run out")
+}
+
+// function to check that b / a in G1 equals d / c in G2, i.e. e(a, d) = e(b, c)
+pub fn same_ratio<E: Pairing>((a, b): (E::G1Affine, E::G1Affine), (c, d): (E::G2Affine, E::G2Affine)) -> bool {
+    E::pairing(a, d) == E::pairing(b, c)
+}
+
+// function to multiply every point of a query by a scalar
+pub fn scale<G: AffineRepr>(points: &[G], factor: G::ScalarField) -> Vec<G> {
+    let scaled: Vec<G::Group> = points.iter().map(|point| *point * factor).collect();
+    G::Group::normalize_batch(&scaled)
+}
+
+// function to multiply each point by its own scalar
+pub fn scale_each<G: AffineRepr>(points: &[G], scalars: &[G::ScalarField]) -> Vec<G> {
+    let scaled: Vec<G::Group> = points.iter().zip(scalars).map(|(point, scalar)| *point * scalar).collect();
+    G::Group::normalize_batch(&scaled)
+}
+
+// function to return 1, s, s^2, ... s^(count - 1)
+pub fn powers<F: PrimeField>(base: F, count: usize) -> Vec<F> {
+    let mut powers = Vec::with_capacity(count);
+    let mut power = F::one();
+    for _ in 0..count {
+        powers.push(power);
+        power *= base;
+    }
+    powers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr as BlsFr, G1Affine};
+    use ark_ff::Field;
+
+    #[test]
+    fn only_consistent_powers_verify() {
+
+        // a fresh transcript has well formed powers, but of a tau everyone knows
+        let mut powers = PowersOfTau::<Bls12_381>::new(8);
+        assert_eq!(powers.check_consistency(), Ok(()));
+        assert_eq!(powers.verify(), Err(VotingError::SetupFailed("phase one has no contributions".to_string())));
+
+        // two participants contribute in turn, each checking the transcript first
+        powers.contribute(&mut thread_rng()).unwrap();
+        assert_eq!(powers.verify(), Ok(()));
+        powers.contribute(&mut thread_rng()).unwrap();
+        assert_eq!(powers.verify(), Ok(()));
+
+        // a power that is not the previous one times tau is caught
+        let mut tampered = powers.clone();
+        tampered.tau_g1[5] = (tampered.tau_g1[5] * BlsFr::from(2)).into_affine();
+        assert_eq!(tampered.verify(), Err(VotingError::InvalidContribution(2)));
+
+        // so is an alpha power moved on its own
+        let mut tampered = powers.clone();
+        tampered.alpha_tau_g1[3] = (tampered.alpha_tau_g1[3] + G1Affine::generator()).into_affine();
+        assert_eq!(tampered.verify(), Err(VotingError::InvalidContribution(2)));
+
+        // a participant who sets tau to a value they chose cannot prove the update
+        let mut forged = powers.clone();
+        let known = BlsFr::from(5);
+        let forged_powers: Vec<BlsFr> = (0..forged.tau_g1.len() as u64).map(|i| known.pow([i])).collect();
+        forged.tau_g1 = scale_each(&PowersOfTau::<Bls12_381>::new(8).tau_g1, &forged_powers);
+        forged.tau_g2 = scale_each(&PowersOfTau::<Bls12_381>::new(8).tau_g2, &forged_powers[..8]);
+        forged.contributions[1].tau_g1 = forged.tau_g1[1];
+        assert_eq!(forged.verify(), Err(VotingError::InvalidContribution(2)));
+    }
+}
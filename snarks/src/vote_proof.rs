@@ -1,79 +1,218 @@
 // import types from libaries and modules
-use ark_bls12_381::{Bls12_381, Fr as BlsFr};
-use ark_groth16::{Groth16, ProvingKey, Proof, VerifyingKey};
-use ark_snark::SNARK;
-use ark_std::{rand::rngs::OsRng, UniformRand};
+use ark_ec::{pairing::PairingOutput, AffineRepr, CurveGroup};
+use ark_groth16::{Groth16, PreparedVerifyingKey, ProvingKey, Proof};
+use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+use ark_std::{rand::rngs::OsRng, UniformRand, Zero};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::time::Instant;
 use ark_serialize::CanonicalSerialize;
-use crate::{circuit::VotingCircuit, voter_registration::Party};
+use voting_core::backend::{ProofBackend, ProofMetrics};
+use voting_core::error::VotingError;
+use crate::{backend::SnarkParams, circuit::{self, VotingCircuit}, curve::{CircuitField, SnarkCurve}, election::{Election, ElectionInput}, merkle_tree::{MerklePath, MerkleTree}, poseidon::{self, COMMITMENT_DOMAIN}, voter_registration::{Voter, VoterCredential}};
 
 // vote structure
-pub struct SnarkVote {
-    pub party: u64, // voted party
-    pub nullifier: BlsFr, // identifier
-    pub proof: Proof<Bls12_381>,  // zk-SNARK proof
-    pub commitment: BlsFr,  // blinding factor commitment
+pub struct SnarkVote<E: SnarkCurve> {
+    pub nullifier: E::ScalarField, // identifier
+    pub proof: Proof<E>,  // zk-SNARK proof
+    pub commitment: E::ScalarField,  // commitment hiding the voted candidate
     pub proof_size: usize,  // proof size
     pub gen_time: u128,    // proof generation time
     pub verify_time: u128, // proof verification time
 }
 
+// opening of a vote commitment, kept by the voter and handed to the tallier
+#[derive(Debug, Clone, Copy)]
+pub struct VoteOpening<F: CircuitField> {
+    pub candidate: u64, // voted candidate
+    pub blinding: F, // commitment randomness
+}
+
+// vote opening implementation
+impl<F: CircuitField> VoteOpening<F> {
+
+    // function to recompute the commitment this opening belongs to
+    pub fn commitment(&self) -> F {
+        poseidon::hash(&[F::from(COMMITMENT_DOMAIN), F::from(self.candidate), self.blinding])
+    }
+
+}
+
 // implement vote
-impl SnarkVote {
+impl<E: SnarkCurve> SnarkVote<E> {
     
-    // function to create a new vote
-    pub fn new(party: Party, nullifier: BlsFr, pk: &ProvingKey<Bls12_381>) -> Self {
+    // function to create a new vote and the private opening of its commitment
+    pub fn new(candidate: usize, voter: &Voter, path: &MerklePath<E::ScalarField>, election: &Election, pk: &ProvingKey<E>) -> Result<(Self, VoteOpening<E::ScalarField>), VotingError> {
 
         // variable initialization
         let mut rng = OsRng;
-        let blinding = BlsFr::rand(&mut rng);
+        let opening = VoteOpening { candidate: candidate as u64, blinding: E::ScalarField::rand(&mut rng) };
+        let commitment = opening.commitment();
+        let election_id = election.field_id();
+        let nullifier = voter.nullifier(election_id);
 
         // voting circuit initialization
         let circuit = VotingCircuit {
-            vote: Some(BlsFr::from(party.as_u64())),
+            vote: Some(E::ScalarField::from(opening.candidate)),
             nullifier_hash: Some(nullifier),
-            blinding: Some(blinding),
+            commitment: Some(commitment),
+            blinding: Some(opening.blinding),
+            root: Some(path.root(voter.leaf())),
+            election_id: Some(election_id),
+            secret: Some(voter.secret()),
+            path: Some(path.clone()),
+            num_candidates: election.num_candidates(),
         };
 
         // proof generation
         let gen_start = Instant::now();
-        let proof = Groth16::<Bls12_381>::prove(pk, circuit, &mut rng)
-            .expect("Proof generation failed.");
+        let proof = Groth16::<E>::prove(pk, circuit, &mut rng)
+            .map_err(|error| VotingError::ProvingFailed(error.to_string()))?;
         let gen_time = gen_start.elapsed().as_nanos();
 
         // proof serialization
         let mut serialized = Vec::new();
         proof.serialize_compressed(&mut serialized)
-            .expect("Proof serialization failed.");
+            .map_err(|error| VotingError::Serialization(error.to_string()))?;
         let proof_size = serialized.len();
 
         // constructed vote with the completed proof
-        Self {
-            party: party.as_u64(),
+        let vote = Self {
             nullifier,
             proof,
-            commitment: blinding,
+            commitment,
             proof_size,
             gen_time,
             verify_time: 0,
+        };
+        Ok((vote, opening))
+    }
+
+    // function to return the public inputs of the proof
+    pub fn public_inputs(&self, root: E::ScalarField, election_id: E::ScalarField) -> [E::ScalarField; 4] {
+        [self.nullifier, self.commitment, root, election_id]
+    }
+
+    // function to check the proof against the prepared key, processing the public inputs once
+    pub fn verify_prepared(&self, params: &SnarkParams<E>, election: &Election) -> Result<(), VotingError> {
+        let inputs = self.public_inputs(params.root, election.field_id());
+        let accepted = Groth16::<E>::prepare_inputs(&params.pvk, &inputs)
+            .and_then(|prepared| Groth16::<E>::verify_proof_with_prepared_inputs(&params.pvk, &self.proof, &prepared))
+            .unwrap_or(false);
+        if accepted { Ok(()) } else { Err(VotingError::InvalidProof) }
+    }
+}
+
+// ballots verified per second by each verification path
+#[derive(Debug, Clone, Copy)]
+pub struct Throughput {
+    pub unprepared: f64, // key prepared again for every ballot
+    pub prepared: f64, // key prepared once
+    pub pool: f64, // key prepared once, ballots spread over the rayon thread pool
+}
+
+// function to verify many proofs with one random linear combination of their pairing checks
+pub fn batch_verify<E: SnarkCurve>(pvk: &PreparedVerifyingKey<E>, root: E::ScalarField, election_id: E::ScalarField, votes: &[&SnarkVote<E>]) -> bool {
+
+    let mut rng = OsRng;
+    let vk = &pvk.vk;
+
+    // one pairing per proof plus two shared pairings
+    let mut g1: Vec<E::G1Prepared> = Vec::with_capacity(votes.len() + 2);
+    let mut g2: Vec<E::G2Prepared> = Vec::with_capacity(votes.len() + 2);
+    let mut weight_sum = E::ScalarField::zero();
+    let mut inputs_sum = E::G1::zero();
+    let mut c_sum = E::G1::zero();
+
+    for vote in votes {
+
+        // every proof must carry one value per public input
+        let inputs = vote.public_inputs(root, election_id);
+        if inputs.len() + 1 != vk.gamma_abc_g1.len() {
+            return false;
+        }
+
+        // random weight for this proof's check e(A, B) = e(alpha, beta) e(L, gamma) e(C, delta)
+        let weight = E::ScalarField::rand(&mut rng);
+        let mut prepared = vk.gamma_abc_g1[0].into_group();
+        for (input, base) in inputs.iter().zip(&vk.gamma_abc_g1[1..]) {
+            prepared += *base * input;
+        }
+        g1.push((vote.proof.a * weight).into_affine().into());
+        g2.push(vote.proof.b.into());
+        inputs_sum += prepared * weight;
+        c_sum += vote.proof.c * weight;
+        weight_sum += weight;
+
+    }
+
+    // the prepared key holds -gamma, -delta and e(alpha, beta), so the product must equal e(alpha, beta) to the weight sum
+    g1.push(inputs_sum.into_affine().into());
+    g2.push(pvk.gamma_g2_neg_pc.clone());
+    g1.push(c_sum.into_affine().into());
+    g2.push(pvk.delta_g2_neg_pc.clone());
+
+    E::final_exponentiation(E::multi_miller_loop(g1, g2))
+        .is_some_and(|product| product == PairingOutput(pvk.alpha_g1_beta_g2) * weight_sum)
+
+}
+
+// function to measure the circuit size and the cost of one vote on a curve for an election with n candidates
+pub fn proving_cost<E: SnarkCurve>(num_candidates: usize) -> Option<(usize, ProofMetrics)> {
+
+    let mut rng = OsRng;
+
+    // keys for an election of this size
+    let constraints = circuit::constraint_count::<E::ScalarField>(num_candidates)?;
+    let (pk, _) = Groth16::<E>::setup(VotingCircuit::empty(num_candidates), &mut rng).ok()?;
+
+    // time a single vote from a voter in a one-leaf registry
+    let mut election = Election::new("proving_cost".to_string(), (0..num_candidates).map(|i| format!("candidate_{}", i)).collect(), Vec::new(), 8).ok()?;
+    election.curve = E::Fr::CURVE;
+    let voter = Voter::new("voter".to_string());
+    let tree = MerkleTree::new(&[voter.leaf()])?;
+    let params = SnarkParams::new(pk, tree.root());
+    let (mut vote, _) = SnarkVote::<E>::new(0, &voter, &tree.path(0)?, &election, &params.pk).ok()?;
+    vote.verify(&params, &election).ok()?;
+    Some((constraints, vote.metrics()))
+
+}
+
+// function to measure how many ballots per second verify, cycling through the given votes until the count is reached
+pub fn verification_throughput<E: SnarkCurve>(params: &SnarkParams<E>, election: &Election, votes: &[SnarkVote<E>], ballots: usize) -> Result<Throughput, VotingError> {
+
+    if votes.is_empty() {
+        return Err(VotingError::InvalidProof);
+    }
+    let election_id = election.field_id();
+    let rate = |elapsed: std::time::Duration| ballots as f64 / elapsed.as_secs_f64();
+
+    // the verifying key is prepared again for every ballot
+    let start = Instant::now();
+    for i in 0..ballots {
+        let vote = &votes[i % votes.len()];
+        if !Groth16::<E>::verify(&params.vk, &vote.public_inputs(params.root, election_id), &vote.proof).unwrap_or(false) {
+            return Err(VotingError::InvalidProof);
         }
     }
+    let unprepared = rate(start.elapsed());
 
-    // function for proof verification
-    pub fn verify(&mut self, vk: &VerifyingKey<Bls12_381>) -> bool {
-
-        // verification time initialization
-        let verify_start = Instant::now();
-        
-        // verify the proof
-        let result = Groth16::<Bls12_381>::verify(
-            vk,
-            &[self.nullifier, self.commitment],  // Public inputs
-            &self.proof
-        ).unwrap_or(false);
-        
-        // measure verification time
-        self.verify_time = verify_start.elapsed().as_nanos();
-        result
+    // the prepared key is shared by every ballot
+    let start = Instant::now();
+    for i in 0..ballots {
+        votes[i % votes.len()].verify_prepared(params, election)?;
     }
-}
\ No newline at end of file
+    let prepared = rate(start.elapsed());
+
+    // the same work spread over the thread pool, sized by RAYON_NUM_THREADS
+    let start = Instant::now();
+    #[cfg(feature = "parallel")]
+    let indices = (0..ballots).into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let mut indices = 0..ballots;
+    indices.try_for_each(|i| votes[i % votes.len()].verify_prepared(params, election))?;
+    let pool = rate(start.elapsed());
+
+    Ok(Throughput { unprepared, prepared, pool })
+
+}
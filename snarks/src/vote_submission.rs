@@ -1,114 +1,226 @@
 // import types from libaries and modules
-use std::collections::HashSet;
-use ark_bls12_381::{Bls12_381, Fr as BlsFr};
-use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
-use ark_snark::CircuitSpecificSetupSNARK;
-use ark_std::{rand::thread_rng, UniformRand};
-use memory_stats::memory_stats;
-use crate::{circuit::VotingCircuit, vote_proof::SnarkVote};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+use ark_groth16::Proof;
+use ark_serialize::CanonicalSerialize;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use voting_core::backend::ProofBackend;
+use voting_core::ballot::Ballot;
+use voting_core::ballot_box::BallotBox;
+use voting_core::error::VotingError;
+use voting_core::tally::Tally;
+use crate::{aggregation::{self, AggregateProof, AggregationKey}, backend::{Fingerprint, SnarkParams}, ceremony::Ceremony, circuit, curve::SnarkCurve, election::{Election, ElectionInput}, merkle_tree::MerklePath, vote_proof::{SnarkVote, VoteOpening}, voter_registration::Voter};
 
 // vote submission structure
-pub struct VoteSubmission {
-    nullifiers: HashSet<BlsFr>, // voter identifier
-    pub pk: ProvingKey<Bls12_381>, // proving key
-    pub vk: VerifyingKey<Bls12_381>, // verifiying key
-    votes: Vec<(u64, BlsFr)>, // vote
-    total_gen_time: u128, // proof generation time
-    total_verify_time: u128, // proof verification time
-    total_proof_size: usize, // size of all proofs
-    total_votes: usize, // number of votes
-    total_memory_used: usize, // memory usage
+pub struct VoteSubmission<E: SnarkCurve> {
+    pub ballot_box: BallotBox<SnarkVote<E>>, // keys, accepted votes, nullifiers and proof metrics
+    total_aggregate_size: usize, // size of the last aggregate proof of the accepted ballots
+    total_aggregate_verify_time: u128, // time to verify the last aggregate proof
 }
 
 // vote submission implementation
-impl VoteSubmission {
-
-    // function to create a new vote submission
-    pub fn new() -> Self {
-
-        let mut rng = thread_rng();
-        
-        // create a circuit for the trusted setup
-        let setup_circuit = VotingCircuit {
-            vote: Some(BlsFr::from(1)),
-            nullifier_hash: Some(BlsFr::rand(&mut rng)),
-            blinding: Some(BlsFr::rand(&mut rng)),
-        };
-
-        // generate proving and verification keys
-        let (pk, vk) = Groth16::<Bls12_381>::setup(setup_circuit, &mut rng)
-            .expect("Trusted setup failed.");
-
-        // structure vote submission
-        Self {
-            nullifiers: HashSet::new(),
-            pk,
-            vk,
-            votes: Vec::new(),
-            total_gen_time: 0,
-            total_verify_time: 0,
-            total_proof_size: 0,
-            total_votes: 0,
-            total_memory_used: 0,
-        }
+impl<E: SnarkCurve> VoteSubmission<E> {
+
+    // function to create a vote submission with keys from a single-party setup, whose randomness is only trusted in tests
+    #[cfg(test)]
+    pub fn new(election: Election, root: E::ScalarField) -> Result<Self, VotingError> {
+        Ok(Self::from_ballot_box(BallotBox::setup(election, root)?))
     }
 
-    // function to submit the vote
-    pub fn submit_vote(&mut self, mut vote: SnarkVote) -> bool {
+    // function to create a vote submission from the keys of a verified setup ceremony
+    pub fn from_ceremony(election: Election, root: E::ScalarField, ceremony: Ceremony<E>) -> Result<Self, VotingError> {
+        let params = ceremony.finish(&election, root)?;
+        Ok(Self::from_ballot_box(BallotBox::new(election, params)))
+    }
 
-        // start measuring memory usage
-        let mem_before = memory_stats().map(|m| m.physical_mem).unwrap_or(0);
+    // function to create a vote submission from keys saved by an earlier setup and their published fingerprint
+    pub fn from_keys(election: Election, root: E::ScalarField, dir: &Path, fingerprint: &Fingerprint) -> Result<Self, VotingError> {
+        let params = SnarkParams::load_keys(dir, &election, root, fingerprint)?;
+        Ok(Self::from_ballot_box(BallotBox::new(election, params)))
+    }
 
-        // ensure voter is not a duplicate voter
-        if self.nullifiers.contains(&vote.nullifier) {
-            return false;
-        }
+    // function to save the election's keys so other processes can prove and verify with them
+    pub fn save_keys(&self, dir: &Path) -> Result<Fingerprint, VotingError> {
+        self.ballot_box.params.save_keys(dir, self.election())
+    }
 
-        // verify the proof
-        if !vote.verify(&self.vk) {
-            return false;
-        }
+    // function to wrap a ballot box whose keys are ready
+    fn from_ballot_box(ballot_box: BallotBox<SnarkVote<E>>) -> Self {
+        Self { ballot_box, total_aggregate_size: 0, total_aggregate_verify_time: 0 }
+    }
+
+    // function to return the election the keys were generated for
+    pub fn election(&self) -> &Election {
+        &self.ballot_box.election
+    }
 
-        // stop measuring memory
-        let mem_after = memory_stats().map(|m| m.physical_mem).unwrap_or(0);
-        self.total_memory_used += mem_after.saturating_sub(mem_before);
+    // function to prove a voter's candidate choice, returning the vote and its private opening
+    pub fn cast(&self, voter: &Voter, candidate: usize, path: &MerklePath<E::ScalarField>) -> Result<(SnarkVote<E>, VoteOpening<E::ScalarField>), VotingError> {
+        self.ballot_box.prove(voter, (Ballot::new(candidate, &[]), path.clone()))
+    }
+
+    // function to submit the vote
+    pub fn submit_vote(&mut self, vote: SnarkVote<E>) -> Result<(), VotingError> {
+        self.ballot_box.submit(vote)
+    }
 
-        // update the measured performance metrics
-        self.total_votes += 1;
-        self.total_gen_time += vote.gen_time;
-        self.total_verify_time += vote.verify_time;
-        self.total_proof_size += vote.proof_size;
+    // function to submit a batch of votes, returning whether each one was accepted
+    pub fn submit_batch(&mut self, votes: Vec<SnarkVote<E>>) -> Vec<Result<(), VotingError>> {
+        self.ballot_box.submit_batch(votes)
+    }
 
-        // record the vote
-        self.nullifiers.insert(vote.nullifier);
-        self.votes.push((vote.party, vote.commitment));
-        true
+    // function to submit votes whose proofs are verified on the rayon thread pool against the prepared key
+    pub fn submit_parallel(&mut self, votes: Vec<SnarkVote<E>>) -> Vec<Result<(), VotingError>> {
+        self.ballot_box.submit_with(votes, |params, election, mut pending| {
+            #[cfg(feature = "parallel")]
+            let pending = pending.par_iter_mut();
+            #[cfg(not(feature = "parallel"))]
+            let pending = pending.iter_mut();
+            pending.map(|vote| vote.verify(params, election)).collect()
+        })
+    }
 
+    // function to aggregate the proofs of every accepted ballot into one proof for auditors
+    pub fn aggregate(&mut self, key: &AggregationKey<E>) -> Result<AggregateProof<E>, VotingError> {
+        let (proofs, inputs) = self.board();
+        let aggregate = aggregation::aggregate(key, &self.ballot_box.params.vk, &proofs, &inputs)?;
+        self.total_aggregate_size = aggregate.compressed_size();
+        Ok(aggregate)
     }
 
-    // function to count the votes for each party
-    pub fn tally(&self) -> Vec<(u64, usize)> {
-        let mut counts = vec![0; 4];
-        for (party, _) in &self.votes {
-            counts[*party as usize] += 1;
+    // function to check an aggregate proof against every accepted ballot with one verify call
+    pub fn verify_aggregate(&mut self, key: &AggregationKey<E>, aggregate: &AggregateProof<E>) -> Result<(), VotingError> {
+        let (_, inputs) = self.board();
+        let verify_start = Instant::now();
+        let result = aggregation::verify(key, &self.ballot_box.params.vk, &inputs, aggregate);
+        self.total_aggregate_verify_time = verify_start.elapsed().as_nanos();
+        result
+    }
+
+    // function to collect the proofs and public inputs of the accepted ballots
+    fn board(&self) -> (Vec<Proof<E>>, Vec<Vec<E::ScalarField>>) {
+        let (root, election_id) = (self.ballot_box.params.root, self.election().field_id());
+        self.ballot_box.votes().iter()
+            .map(|vote| (vote.proof.clone(), vote.public_inputs(root, election_id).to_vec()))
+            .unzip()
+    }
+
+    // function to count the votes for each candidate, failing unless every accepted commitment is opened exactly once
+    pub fn tally(&self, openings: &[VoteOpening<E::ScalarField>]) -> Result<Tally, VotingError> {
+
+        // every opening must match a distinct accepted commitment
+        let votes = self.ballot_box.votes();
+        if openings.len() != votes.len() {
+            return Err(VotingError::InvalidOpening);
         }
-        counts.into_iter().enumerate().map(|(i, c)| (i as u64, c)).collect()
+        let mut unopened: HashMap<E::ScalarField, usize> = HashMap::new();
+        for vote in votes {
+            *unopened.entry(vote.commitment).or_insert(0) += 1;
+        }
+
+        // count the candidate behind each opened commitment
+        let mut counts = vec![0; self.election().num_candidates()];
+        for opening in openings {
+            let remaining = unopened.get_mut(&opening.commitment()).ok_or(VotingError::InvalidOpening)?;
+            if *remaining == 0 || opening.candidate as usize >= counts.len() {
+                return Err(VotingError::InvalidOpening);
+            }
+            *remaining -= 1;
+            counts[opening.candidate as usize] += 1;
+        }
+
+        Ok(Tally { candidates: counts, contests: Vec::new() })
+
     }
 
     // function to display the performance metrics
     pub fn display_metrics(&self) {
 
-        println!("\n=== Performance Metrics ===");
-        println!("Number of voted processed: {}", self.total_votes);
-        println!("Average proof generation time: {:.2} ms", 
-            self.total_gen_time as f64 / self.total_votes as f64 / 1_000_000.0);
-        println!("Average verification time: {:.2} ms", 
-            self.total_verify_time as f64 / self.total_votes as f64 / 1_000_000.0);
-        println!("Average proof size: {} bytes", 
-            self.total_proof_size / self.total_votes);
-        println!("Total memory usage: {} KB", 
-            self.total_memory_used / 1024);
+        self.ballot_box.display_metrics();
+        if self.ballot_box.metrics.total_votes == 0 {
+            return;
+        }
+
+        // one aggregate replaces a proof and a pairing check per ballot
+        if self.total_aggregate_size > 0 {
+            println!("Aggregate proof size for {} ballots: {} bytes, against {} bytes of individual proofs",
+                self.ballot_box.votes().len(), self.total_aggregate_size, self.ballot_box.metrics.total_proof_size);
+            println!("Aggregate verification time: {:.2} ms, against {:.2} ms per ballot",
+                self.total_aggregate_verify_time as f64 / 1_000_000.0,
+                self.ballot_box.metrics.total_verify_time as f64 / self.ballot_box.metrics.total_votes as f64 / 1_000_000.0);
+        }
+
+        if let Some(constraints) = circuit::constraint_count::<E::ScalarField>(self.election().num_candidates()) {
+            println!("Circuit constraints for {} candidates: {}", self.election().num_candidates(), constraints);
+        }
+
+    }
+
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr as BlsFr};
+    use crate::voter_registration::{RegistrationAuthority, VoterCredential};
+
+    // function to define the election used by the tests
+    fn election() -> Election {
+        Election::new("election_1".to_string(), vec!["a".to_string(), "b".to_string(), "c".to_string()], Vec::new(), 8).unwrap()
     }
 
+    #[test]
+    fn recreated_voter_is_caught_as_duplicate() {
+        let voter = Voter::new("voter".to_string());
+        let mut authority = RegistrationAuthority::<BlsFr>::new();
+        let index = authority.register(&voter).unwrap();
+        let path = authority.path(index).unwrap();
+        let mut submission = VoteSubmission::<Bls12_381>::new(election(), authority.root()).unwrap();
+
+        let (first, _) = submission.cast(&voter, 0, &path).unwrap();
+        assert_eq!(submission.submit_vote(first), Ok(()));
+
+        let recreated = Voter::from_key("voter".to_string(), voter.export_key());
+        let (second, _) = submission.cast(&recreated, 2, &path).unwrap();
+        assert_eq!(submission.submit_vote(second), Err(VotingError::DuplicateNullifier));
+    }
+
+    #[test]
+    fn tally_only_accepts_the_committed_candidate() {
+        let voter = Voter::new("voter".to_string());
+        let mut authority = RegistrationAuthority::<BlsFr>::new();
+        let index = authority.register(&voter).unwrap();
+        let path = authority.path(index).unwrap();
+        let mut submission = VoteSubmission::<Bls12_381>::new(election(), authority.root()).unwrap();
+
+        assert_eq!(submission.cast(&voter, 3, &path).err(), Some(VotingError::InvalidCandidate { candidate: 3, num_candidates: 3 }));
+        let (vote, opening) = submission.cast(&voter, 1, &path).unwrap();
+        assert_eq!(submission.submit_vote(vote), Ok(()));
+        assert_eq!(submission.tally(&[opening]), Ok(Tally { candidates: vec![0, 1, 0], contests: Vec::new() }));
+
+        let forged = VoteOpening { candidate: 2, ..opening };
+        assert_eq!(submission.tally(&[forged]), Err(VotingError::InvalidOpening));
+        assert_eq!(submission.tally(&[opening, opening]), Err(VotingError::InvalidOpening));
+    }
+
+    #[test]
+    fn forged_ballot_is_found_inside_a_batch() {
+        let voters: Vec<Voter> = (0..3).map(|i| Voter::new(format!("voter_{}", i))).collect();
+        let mut authority = RegistrationAuthority::<BlsFr>::new();
+        let indices: Vec<usize> = voters.iter().map(|voter| authority.register(voter).unwrap()).collect();
+        let mut submission = VoteSubmission::<Bls12_381>::new(election(), authority.root()).unwrap();
+        let mut batch: Vec<SnarkVote<Bls12_381>> = voters.iter().zip(indices).enumerate()
+            .map(|(candidate, (voter, index))| submission.cast(voter, candidate, &authority.path(index).unwrap()).unwrap().0)
+            .collect();
+
+        // the middle proof is moved onto another nullifier, so the pairing product over the batch fails
+        batch[1].nullifier = Voter::new("replayed_voter".to_string()).nullifier(submission.election().field_id());
+        let votes: Vec<&SnarkVote<Bls12_381>> = batch.iter().collect();
+        assert!(!SnarkVote::batch_verify(&submission.ballot_box.params, submission.election(), &votes));
+
+        // the fallback checks each ballot and flags only the forged one
+        assert_eq!(submission.submit_batch(batch), vec![Ok(()), Err(VotingError::InvalidProof), Ok(())]);
+    }
 }
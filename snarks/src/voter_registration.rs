@@ -1,69 +1,116 @@
 // import types from libaries and modules
-use ark_bls12_381::Fr as BlsFr;
-use ark_ff::{BigInteger, PrimeField, UniformRand};
-use rand::rngs::OsRng;
-use sha3::{Sha3_256, Digest};
-
-// voter structure
-#[derive(Debug, Clone)]
-pub struct Voter {
-    pub id: String, // voter ID
-    id_blinding: BlsFr, // blinding factor
+use std::collections::HashSet;
+use crate::curve::CircuitField;
+use crate::merkle_tree::{MerklePath, MerkleTree};
+use crate::poseidon::{self, LEAF_DOMAIN, NULLIFIER_DOMAIN};
+use voting_core::error::VotingError;
+pub use voting_core::voter::Voter;
+
+// circuit credentials of a voter in this backend
+pub trait VoterCredential {
+
+    // function to return the secret used as a circuit witness
+    fn secret<F: CircuitField>(&self) -> F;
+
+    // function to compute the leaf committed into the registry
+    fn leaf<F: CircuitField>(&self) -> F;
+
+    // function to compute a voters unique identifier for one election
+    fn nullifier<F: CircuitField>(&self, election_id: F) -> F;
+
+}
+
+// registration authority structure
+pub struct RegistrationAuthority<F: CircuitField> {
+    ids: HashSet<String>, // registered voter IDs
+    leaves: Vec<F>, // registered leaves in registration order
+    tree: MerkleTree<F>, // tree over the registered leaves
 }
 
-// voter implementation
-impl Voter {
-    
-    // function to create a new voter
-    pub fn new(id: String) -> Self {
+// voter credential implementation
+impl VoterCredential for Voter {
 
-        let mut rng = OsRng;
+    fn secret<F: CircuitField>(&self) -> F {
+        F::from_le_bytes_mod_order(&self.derive_secret(F::CURVE.to_string().as_bytes()))
+    }
+
+    fn leaf<F: CircuitField>(&self) -> F {
+        poseidon::hash(&[F::from(LEAF_DOMAIN), self.secret()])
+    }
+
+    fn nullifier<F: CircuitField>(&self, election_id: F) -> F {
+        poseidon::hash(&[F::from(NULLIFIER_DOMAIN), self.secret(), election_id])
+    }
+
+}
 
-        // constructed voter
+// registration authority implementation
+impl<F: CircuitField> RegistrationAuthority<F> {
+
+    // function to create an empty registry
+    pub fn new() -> Self {
         Self {
-            id,
-            id_blinding: BlsFr::rand(&mut rng),
+            ids: HashSet::new(),
+            leaves: Vec::new(),
+            tree: MerkleTree::new(&[]).expect("Empty registry must fit in the tree."),
         }
+    }
+
+    // function to register an eligible voter, returning their leaf index
+    pub fn register(&mut self, voter: &Voter) -> Result<usize, VotingError> {
+
+        // each voter ID may only be registered once
+        if self.ids.contains(&voter.id) {
+            return Err(VotingError::DuplicateRegistration(voter.id.clone()));
+        }
+
+        // rebuild the tree with the new leaf
+        let mut leaves = self.leaves.clone();
+        leaves.push(voter.leaf());
+        self.tree = MerkleTree::new(&leaves).ok_or(VotingError::RegistryFull)?;
+        self.leaves = leaves;
+        self.ids.insert(voter.id.clone());
+        Ok(self.leaves.len() - 1)
 
     }
 
-    // function to compute a voters unique identifier
-    pub fn nullifier(&self) -> BlsFr {
-        let mut hash = Sha3_256::new();
-        hash.update(self.id.as_bytes());
-        hash.update(self.id_blinding.into_bigint().to_bytes_le());
-        BlsFr::from_le_bytes_mod_order(&hash.finalize())
+    // function to return the published registry root
+    pub fn root(&self) -> F {
+        self.tree.root()
     }
 
-}
+    // function to return the authentication path of a registered voter
+    pub fn path(&self, index: usize) -> Result<MerklePath<F>, VotingError> {
+        if index >= self.leaves.len() {
+            return Err(VotingError::NotRegistered);
+        }
+        self.tree.path(index).ok_or(VotingError::NotRegistered)
+    }
 
-// party enum
-#[derive(Debug, Copy, Clone, PartialEq)]
-#[allow(dead_code)]
-pub enum Party {
-    Liberal = 0,
-    Conservative = 1,
-    NDP = 2,
-    Green = 3,
 }
 
-// party implementation
-impl Party {
-    
-    // function to convert the enum party to u64
-    pub fn as_u64(&self) -> u64 {
-        *self as u64
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr as BlsFr;
+
+    #[test]
+    fn nullifier_is_deterministic_per_key_and_election() {
+        let voter = Voter::new("voter".to_string());
+        let recreated = Voter::from_key("voter".to_string(), voter.export_key());
+        let (election_1, election_2) = (BlsFr::from(1), BlsFr::from(2));
+        assert_eq!(voter.nullifier(election_1), recreated.nullifier(election_1));
+        assert_ne!(voter.nullifier(election_1), voter.nullifier(election_2));
     }
-    
-    // function to convert the u64 part to an enum
-    pub fn from_u64(value: u64) -> Self {
-        match value {
-            0 => Party::Liberal,
-            1 => Party::Conservative,
-            2 => Party::NDP,
-            3 => Party::Green,
-            _ => panic!("Invalid party value"),
-        }
+
+    #[test]
+    fn recreated_voter_keeps_their_registry_leaf() {
+        let voter = Voter::new("voter".to_string());
+        let mut authority = RegistrationAuthority::<BlsFr>::new();
+        let index = authority.register(&voter).unwrap();
+        let recreated = Voter::from_key("voter".to_string(), voter.export_key());
+        assert_eq!(authority.path(index).unwrap().root(recreated.leaf()), authority.root());
+        assert_eq!(authority.register(&recreated), Err(VotingError::DuplicateRegistration("voter".to_string())));
+        assert_eq!(authority.path(index + 1).err(), Some(VotingError::NotRegistered));
     }
-    
-}
\ No newline at end of file
+}
@@ -0,0 +1,44 @@
+// import types from libaries and modules
+use crate::election::Election;
+use crate::error::VotingError;
+use crate::voter::Voter;
+
+// public per-election identifier of a voter, used to detect double voting
+pub type Nullifier = [u8; 32];
+
+// performance measurements carried by a proof
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProofMetrics {
+    pub gen_time: u128, // proof generation time
+    pub verify_time: u128, // proof verification time
+    pub proof_size: usize, // proof size
+}
+
+// proof system that turns a ballot into a publicly verifiable vote
+pub trait ProofBackend: Sized {
+    type Setup; // backend specific setup inputs, such as an election key or registry root
+    type Params; // public parameters every vote is proven and checked against
+    type Witness; // private ballot and credentials behind one vote
+    type Opening; // private data the voter keeps after proving
+
+    // function to derive the public parameters for an election
+    fn setup(election: &Election, setup: Self::Setup) -> Result<Self::Params, VotingError>;
+
+    // function to prove a ballot for the election
+    fn prove(params: &Self::Params, election: &Election, voter: &Voter, witness: Self::Witness) -> Result<(Self, Self::Opening), VotingError>;
+
+    // function to verify a vote, recording the verification time
+    fn verify(&mut self, params: &Self::Params, election: &Election) -> Result<(), VotingError>;
+
+    // function to verify many votes at once, false if any of them is invalid
+    fn batch_verify(params: &Self::Params, election: &Election, votes: &[&Self]) -> bool;
+
+    // function to serialize the proof for publication
+    fn serialize(&self) -> Result<Vec<u8>, VotingError>;
+
+    // function to return the nullifier the vote was cast under
+    fn nullifier(&self) -> Nullifier;
+
+    // function to return the measured performance of the proof
+    fn metrics(&self) -> ProofMetrics;
+}
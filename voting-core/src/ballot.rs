@@ -0,0 +1,16 @@
+// plaintext choices on one ballot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ballot {
+    pub candidate: usize, // index of the chosen candidate
+    pub answers: Vec<u64>, // answer to each contest in ballot order
+}
+
+// ballot implementation
+impl Ballot {
+
+    // function to create a ballot
+    pub fn new(candidate: usize, answers: &[u64]) -> Self {
+        Self { candidate, answers: answers.to_vec() }
+    }
+
+}
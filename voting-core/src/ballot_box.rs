@@ -0,0 +1,358 @@
+// import types from libaries and modules
+use std::collections::HashSet;
+use std::time::Instant;
+use memory_stats::memory_stats;
+use crate::backend::{Nullifier, ProofBackend};
+use crate::election::Election;
+use crate::error::VotingError;
+use crate::voter::Voter;
+
+// performance metrics over every accepted vote
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    pub total_gen_time: u128, // proof generation time
+    pub total_verify_time: u128, // proof verification time
+    pub total_proof_size: usize, // size of all proofs
+    pub total_votes: usize, // number of votes
+    pub total_memory_used: usize, // memory usage
+    pub total_batch_verify_time: u128, // batch verification time
+    pub total_batch_votes: usize, // number of ballots verified in batches
+}
+
+// ballot box structure, the submission engine shared by every backend
+pub struct BallotBox<B: ProofBackend> {
+    pub election: Election, // election the ballots are cast in
+    pub params: B::Params, // public parameters every vote is checked against
+    nullifiers: HashSet<Nullifier>, // nullifiers of the accepted votes
+    votes: Vec<B>, // accepted votes in submission order
+    closed: bool, // whether the box stopped taking votes before the window ended
+    pub metrics: Metrics, // measured performance
+}
+
+// function to read the current physical memory usage
+fn physical_memory() -> usize {
+    memory_stats().map(|m| m.physical_mem).unwrap_or(0)
+}
+
+// ballot box implementation
+impl<B: ProofBackend> BallotBox<B> {
+
+    // function to create a ballot box from existing parameters
+    pub fn new(election: Election, params: B::Params) -> Self {
+        Self {
+            election,
+            params,
+            nullifiers: HashSet::new(),
+            votes: Vec::new(),
+            closed: false,
+            metrics: Metrics::default(),
+        }
+    }
+
+    // function to run the backend setup for an election and open a ballot box with it
+    pub fn setup(election: Election, setup: B::Setup) -> Result<Self, VotingError> {
+        let params = B::setup(&election, setup)?;
+        Ok(Self::new(election, params))
+    }
+
+    // function to check whether votes are currently accepted
+    pub fn is_accepting(&self) -> bool {
+        !self.closed && self.election.is_open()
+    }
+
+    // function to stop accepting votes, for example once mixing has started
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    // function to return the accepted votes
+    pub fn votes(&self) -> &[B] {
+        &self.votes
+    }
+
+    // function to prove a voter's ballot against this box's election and parameters
+    pub fn prove(&self, voter: &Voter, witness: B::Witness) -> Result<(B, B::Opening), VotingError> {
+        B::prove(&self.params, &self.election, voter, witness)
+    }
+
+    // function to submit the vote
+    pub fn submit(&mut self, mut vote: B) -> Result<(), VotingError> {
+
+        // start measuring memory usage
+        let mem_before = physical_memory();
+
+        // ballots are only cast while the box is open
+        if !self.is_accepting() {
+            return Err(VotingError::ElectionClosed);
+        }
+
+        // ensure voter is not a duplicate voter
+        if self.nullifiers.contains(&vote.nullifier()) {
+            return Err(VotingError::DuplicateNullifier);
+        }
+
+        // verify the proof
+        vote.verify(&self.params, &self.election)?;
+
+        // record the vote
+        let verify_time = vote.metrics().verify_time;
+        self.record(vote, verify_time);
+
+        // stop measuring memory
+        self.metrics.total_memory_used += physical_memory().saturating_sub(mem_before);
+
+        Ok(())
+
+    }
+
+    // function to submit a batch of votes, returning whether each one was accepted
+    pub fn submit_batch(&mut self, mut votes: Vec<B>) -> Vec<Result<(), VotingError>> {
+
+        // start measuring memory usage
+        let mem_before = physical_memory();
+
+        // reject duplicate voters, including duplicates inside the batch
+        let mut results = self.screen(&votes);
+
+        // verify every remaining proof in one backend batch
+        let verify_start = Instant::now();
+        let pending: Vec<&B> = votes.iter().zip(&results)
+            .filter(|(_, result)| result.is_ok())
+            .map(|(vote, _)| vote)
+            .collect();
+
+        // on failure fall back to checking each proof to find the invalid ones
+        if !B::batch_verify(&self.params, &self.election, &pending) {
+            for (vote, result) in votes.iter_mut().zip(results.iter_mut()) {
+                if result.is_ok() {
+                    *result = vote.verify(&self.params, &self.election);
+                }
+            }
+        }
+        let batch_time = verify_start.elapsed().as_nanos();
+
+        // record the accepted votes with their amortized verification time
+        let verified = results.iter().filter(|result| result.is_ok()).count();
+        let amortized_time = batch_time / verified.max(1) as u128;
+        for (vote, result) in votes.drain(..).zip(&results) {
+            if result.is_ok() {
+                self.record(vote, amortized_time);
+            }
+        }
+        self.metrics.total_batch_verify_time += batch_time;
+        self.metrics.total_batch_votes += verified;
+
+        // stop measuring memory
+        self.metrics.total_memory_used += physical_memory().saturating_sub(mem_before);
+
+        results
+
+    }
+
+    // function to submit votes whose proofs are checked one by one by the given verifier, such as a thread pool, returning whether each one was accepted
+    pub fn submit_with<V>(&mut self, mut votes: Vec<B>, verify: V) -> Vec<Result<(), VotingError>>
+    where V: FnOnce(&B::Params, &Election, Vec<&mut B>) -> Vec<Result<(), VotingError>> {
+
+        // start measuring memory usage
+        let mem_before = physical_memory();
+
+        // reject duplicate voters, then hand the remaining votes to the verifier
+        let mut results = self.screen(&votes);
+        let pending: Vec<&mut B> = votes.iter_mut().zip(&results)
+            .filter(|(_, result)| result.is_ok())
+            .map(|(vote, _)| vote)
+            .collect();
+        let mut verified = verify(&self.params, &self.election, pending).into_iter();
+
+        // a vote the verifier returned no result for is not accepted
+        for result in results.iter_mut().filter(|result| result.is_ok()) {
+            *result = verified.next().unwrap_or(Err(VotingError::InvalidProof));
+        }
+
+        // record the accepted votes with their own verification time
+        for (vote, result) in votes.drain(..).zip(&results) {
+            if result.is_ok() {
+                let verify_time = vote.metrics().verify_time;
+                self.record(vote, verify_time);
+            }
+        }
+
+        // stop measuring memory
+        self.metrics.total_memory_used += physical_memory().saturating_sub(mem_before);
+
+        results
+
+    }
+
+    // function to reject votes cast while the box is closed or under a nullifier already used, including earlier in the same list
+    fn screen(&self, votes: &[B]) -> Vec<Result<(), VotingError>> {
+        let mut seen = HashSet::new();
+        let open = self.is_accepting();
+        votes.iter().map(|vote| {
+            if !open {
+                Err(VotingError::ElectionClosed)
+            } else if self.nullifiers.contains(&vote.nullifier()) || !seen.insert(vote.nullifier()) {
+                Err(VotingError::DuplicateNullifier)
+            } else {
+                Ok(())
+            }
+        }).collect()
+    }
+
+    // function to record an accepted vote and its performance metrics
+    fn record(&mut self, vote: B, verify_time: u128) {
+
+        // update the measured performance metrics
+        let metrics = vote.metrics();
+        self.metrics.total_votes += 1;
+        self.metrics.total_gen_time += metrics.gen_time;
+        self.metrics.total_verify_time += verify_time;
+        self.metrics.total_proof_size += metrics.proof_size;
+
+        // record the vote
+        self.nullifiers.insert(vote.nullifier());
+        self.votes.push(vote);
+
+    }
+
+    // function to display the performance metrics
+    pub fn display_metrics(&self) {
+
+        println!("\n\n--- Performance Metrics ---");
+
+        // zero vote error guard
+        let metrics = &self.metrics;
+        if metrics.total_votes == 0 {
+            println!("No valid votes were submitted.");
+            return;
+        }
+
+        println!("Number of voted processed: {}", metrics.total_votes);
+        println!("Average proof generation time: {:.2} ms",
+            metrics.total_gen_time as f64 / metrics.total_votes as f64 / 1_000_000.0);
+        println!("Average verification time: {:.2} ms",
+            metrics.total_verify_time as f64 / metrics.total_votes as f64 / 1_000_000.0);
+        if metrics.total_batch_votes > 0 {
+            println!("Amortized batch verification time: {:.2} ms per ballot",
+                metrics.total_batch_verify_time as f64 / metrics.total_batch_votes as f64 / 1_000_000.0);
+        }
+        println!("Average proof size: {} bytes",
+            metrics.total_proof_size / metrics.total_votes);
+        println!("Total memory usage: {} KB",
+            metrics.total_memory_used / 1024);
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::ProofMetrics;
+
+    // backend whose proofs are a validity flag, to exercise the engine on its own
+    struct FlagVote {
+        nullifier: Nullifier, // voter identifier
+        valid: bool, // whether the proof verifies
+    }
+
+    // flag backend implementation
+    impl ProofBackend for FlagVote {
+        type Setup = ();
+        type Params = ();
+        type Witness = bool;
+        type Opening = ();
+
+        fn setup(_: &Election, _: ()) -> Result<(), VotingError> {
+            Ok(())
+        }
+
+        fn prove(_: &(), election: &Election, voter: &Voter, valid: bool) -> Result<(Self, ()), VotingError> {
+            let mut nullifier = [0u8; 32];
+            nullifier.copy_from_slice(&voter.derive_secret(election.id.as_bytes())[..32]);
+            Ok((Self { nullifier, valid }, ()))
+        }
+
+        fn verify(&mut self, _: &(), _: &Election) -> Result<(), VotingError> {
+            if self.valid { Ok(()) } else { Err(VotingError::InvalidProof) }
+        }
+
+        fn batch_verify(_: &(), _: &Election, votes: &[&Self]) -> bool {
+            votes.iter().all(|vote| vote.valid)
+        }
+
+        fn serialize(&self) -> Result<Vec<u8>, VotingError> {
+            Ok(vec![self.valid as u8])
+        }
+
+        fn nullifier(&self) -> Nullifier {
+            self.nullifier
+        }
+
+        fn metrics(&self) -> ProofMetrics {
+            ProofMetrics { proof_size: 1, ..ProofMetrics::default() }
+        }
+    }
+
+    // function to open a ballot box for the flag backend
+    fn ballot_box() -> BallotBox<FlagVote> {
+        let election = Election::new("election_1".to_string(), vec!["a".to_string(), "b".to_string()], Vec::new(), 8).unwrap();
+        BallotBox::setup(election, ()).unwrap()
+    }
+
+    // function to cast a flag vote
+    fn vote(ballot_box: &BallotBox<FlagVote>, voter: &Voter, valid: bool) -> FlagVote {
+        ballot_box.prove(voter, valid).unwrap().0
+    }
+
+    #[test]
+    fn recreated_voter_is_caught_as_duplicate() {
+        let mut ballot_box = ballot_box();
+        let voter = Voter::new("voter".to_string());
+        let recreated = Voter::from_key("voter".to_string(), voter.export_key());
+        assert_eq!(ballot_box.submit(vote(&ballot_box, &voter, true)), Ok(()));
+        assert_eq!(ballot_box.submit(vote(&ballot_box, &recreated, true)), Err(VotingError::DuplicateNullifier));
+        assert_eq!(ballot_box.votes().len(), 1);
+    }
+
+    #[test]
+    fn batch_falls_back_to_find_invalid_and_duplicate_votes() {
+        let mut ballot_box = ballot_box();
+        let voters: Vec<Voter> = (0..3).map(|i| Voter::new(format!("voter_{}", i))).collect();
+        let batch = vec![
+            vote(&ballot_box, &voters[0], true),
+            vote(&ballot_box, &voters[1], false),
+            vote(&ballot_box, &voters[2], true),
+            vote(&ballot_box, &voters[0], true),
+        ];
+        let results = ballot_box.submit_batch(batch);
+        assert_eq!(results, vec![Ok(()), Err(VotingError::InvalidProof), Ok(()), Err(VotingError::DuplicateNullifier)]);
+        assert_eq!(ballot_box.metrics.total_batch_votes, 2);
+    }
+
+    #[test]
+    fn verifier_only_sees_screened_votes() {
+        let mut ballot_box = ballot_box();
+        let voters: Vec<Voter> = (0..2).map(|i| Voter::new(format!("voter_{}", i))).collect();
+        let votes = vec![
+            vote(&ballot_box, &voters[0], true),
+            vote(&ballot_box, &voters[0], true),
+            vote(&ballot_box, &voters[1], false),
+        ];
+        let results = ballot_box.submit_with(votes, |params, election, pending| {
+            assert_eq!(pending.len(), 2);
+            pending.into_iter().map(|vote| vote.verify(params, election)).collect()
+        });
+        assert_eq!(results, vec![Ok(()), Err(VotingError::DuplicateNullifier), Err(VotingError::InvalidProof)]);
+        assert_eq!(ballot_box.votes().len(), 1);
+    }
+
+    #[test]
+    fn closed_box_rejects_votes() {
+        let mut ballot_box = ballot_box();
+        ballot_box.close();
+        let voter = Voter::new("voter".to_string());
+        assert_eq!(ballot_box.submit(vote(&ballot_box, &voter, true)), Err(VotingError::ElectionClosed));
+        assert_eq!(ballot_box.submit_batch(vec![vote(&ballot_box, &voter, true)]), vec![Err(VotingError::ElectionClosed)]);
+    }
+}
@@ -0,0 +1,297 @@
+// import types from libaries and modules
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use sha3::{Sha3_256, Digest};
+use thiserror::Error;
+
+// candidate entry in an election manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidateEntry {
+    pub id: String, // stable candidate identifier
+    pub name: String, // name printed on the ballot
+}
+
+// contest entry in an election manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContestEntry {
+    pub id: String, // stable contest identifier
+    pub question: String, // question printed on the ballot
+}
+
+// pairing curves a zk-SNARK backend can prove over
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Curve {
+    #[default]
+    #[serde(rename = "bls12-381")]
+    Bls12_381, // default curve
+    #[serde(rename = "bn254")]
+    Bn254, // cheaper pairings, widely supported by verifiers
+    #[serde(rename = "bls12-377")]
+    Bls12_377, // suited to recursive proofs
+}
+
+// proof system parameters in an election manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofParameters {
+    pub contest_bits: usize, // answers to each contest lie in [0, 2^contest_bits)
+    pub registry_depth: usize, // depth of the voter registry tree
+    #[serde(default)]
+    pub curve: Curve, // pairing curve used by zk-SNARK backends
+}
+
+// election manifest as published before voting opens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub id: String, // election identifier
+    pub title: String, // human readable election title
+    pub opens_at: u64, // start of the voting window in unix seconds
+    pub closes_at: u64, // end of the voting window in unix seconds
+    pub candidates: Vec<CandidateEntry>, // candidates in ballot order
+    #[serde(default)]
+    pub contests: Vec<ContestEntry>, // additional questions answered on the same ballot
+    pub parameters: ProofParameters, // proof system parameters
+}
+
+// reasons an election manifest can be rejected
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ManifestError {
+    #[error("could not read manifest: {0}")]
+    Io(String), // manifest file could not be read
+    #[error("could not parse manifest: {0}")]
+    Parse(String), // manifest is not valid toml for the expected layout
+    #[error("manifest field {0} is empty")]
+    EmptyField(&'static str), // a required text field is empty
+    #[error("manifest lists no candidates")]
+    NoCandidates, // the ballot lists no candidates
+    #[error("candidate {0} is listed twice")]
+    DuplicateCandidate(String), // two candidates share an identifier
+    #[error("contest {0} is listed twice")]
+    DuplicateContest(String), // two contests share an identifier
+    #[error("voting window closes before it opens")]
+    InvalidWindow, // voting window closes before it opens
+    #[error("contest answers cannot be {0} bits, use 8, 16 or 32")]
+    UnsupportedContestBits(usize), // range proofs support 8, 16, 32 or 64 bits, and totals past 32 bits cannot be decrypted
+    #[error("registry depth {0} is not supported, the backend uses {1}")]
+    UnsupportedRegistryDepth(usize, usize), // requested depth and the depth the backend supports
+    #[error("the election uses {0} but the backend was built for {1}")]
+    CurveMismatch(Curve, Curve), // requested curve and the curve the backend was instantiated with
+}
+
+// election structure
+#[derive(Debug, Clone)]
+pub struct Election {
+    pub id: String, // election identifier
+    pub title: String, // human readable election title
+    pub candidates: Vec<String>, // candidate names in ballot order
+    pub contests: Vec<String>, // additional questions answered on the same ballot
+    pub contest_bits: usize, // answers to each question lie in [0, 2^contest_bits)
+    pub registry_depth: usize, // depth of the voter registry tree
+    pub curve: Curve, // pairing curve used by zk-SNARK backends
+    pub opens_at: u64, // start of the voting window in unix seconds
+    pub closes_at: u64, // end of the voting window in unix seconds
+    pub manifest_hash: [u8; 32], // hash of the manifest the election was loaded from
+}
+
+// curve implementation
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bls12_381 => write!(f, "bls12-381"),
+            Self::Bn254 => write!(f, "bn254"),
+            Self::Bls12_377 => write!(f, "bls12-377"),
+        }
+    }
+}
+
+// manifest implementation
+impl Manifest {
+
+    // function to check the manifest describes a well formed election
+    pub fn validate(&self) -> Result<(), ManifestError> {
+
+        // required text fields
+        if self.id.is_empty() {
+            return Err(ManifestError::EmptyField("id"));
+        }
+        if self.title.is_empty() {
+            return Err(ManifestError::EmptyField("title"));
+        }
+
+        // candidates and contests must be present and uniquely identified
+        if self.candidates.is_empty() {
+            return Err(ManifestError::NoCandidates);
+        }
+        let mut ids = HashSet::new();
+        for candidate in &self.candidates {
+            if candidate.id.is_empty() || candidate.name.is_empty() {
+                return Err(ManifestError::EmptyField("candidates"));
+            }
+            if !ids.insert(&candidate.id) {
+                return Err(ManifestError::DuplicateCandidate(candidate.id.clone()));
+            }
+        }
+        let mut ids = HashSet::new();
+        for contest in &self.contests {
+            if contest.id.is_empty() || contest.question.is_empty() {
+                return Err(ManifestError::EmptyField("contests"));
+            }
+            if !ids.insert(&contest.id) {
+                return Err(ManifestError::DuplicateContest(contest.id.clone()));
+            }
+        }
+
+        // voting window and proof parameters
+        if self.closes_at <= self.opens_at {
+            return Err(ManifestError::InvalidWindow);
+        }
+        if ![8, 16, 32].contains(&self.parameters.contest_bits) {
+            return Err(ManifestError::UnsupportedContestBits(self.parameters.contest_bits));
+        }
+
+        Ok(())
+
+    }
+
+    // function to hash the canonical serialization of the manifest
+    pub fn hash(&self) -> Result<[u8; 32], ManifestError> {
+        let canonical = toml::to_string(self).map_err(|error| ManifestError::Parse(error.to_string()))?;
+        Ok(Sha3_256::digest(canonical.as_bytes()).into())
+    }
+
+}
+
+// election implementation
+impl Election {
+
+    // function to create an always open election, with names doubling as identifiers
+    pub fn new(id: String, candidates: Vec<String>, contests: Vec<String>, contest_bits: usize) -> Result<Self, ManifestError> {
+        Self::from_manifest(Manifest {
+            title: id.clone(),
+            id,
+            opens_at: 0,
+            closes_at: i64::MAX as u64, // latest time a toml manifest can hold
+            candidates: candidates.into_iter().map(|name| CandidateEntry { id: name.clone(), name }).collect(),
+            contests: contests.into_iter().map(|question| ContestEntry { id: question.clone(), question }).collect(),
+            parameters: ProofParameters { contest_bits, registry_depth: 8, curve: Curve::default() },
+        })
+    }
+
+    // function to load an election from a toml manifest file
+    pub fn load(path: &str) -> Result<Self, ManifestError> {
+        let text = std::fs::read_to_string(path).map_err(|error| ManifestError::Io(error.to_string()))?;
+        Self::parse(&text)
+    }
+
+    // function to parse an election from toml manifest text
+    pub fn parse(text: &str) -> Result<Self, ManifestError> {
+        let manifest: Manifest = toml::from_str(text).map_err(|error| ManifestError::Parse(error.message().to_string()))?;
+        Self::from_manifest(manifest)
+    }
+
+    // function to build an election from a validated manifest
+    pub fn from_manifest(manifest: Manifest) -> Result<Self, ManifestError> {
+        manifest.validate()?;
+        let manifest_hash = manifest.hash()?;
+        Ok(Self {
+            id: manifest.id,
+            title: manifest.title,
+            candidates: manifest.candidates.into_iter().map(|candidate| candidate.name).collect(),
+            contests: manifest.contests.into_iter().map(|contest| contest.question).collect(),
+            contest_bits: manifest.parameters.contest_bits,
+            registry_depth: manifest.parameters.registry_depth,
+            curve: manifest.parameters.curve,
+            opens_at: manifest.opens_at,
+            closes_at: manifest.closes_at,
+            manifest_hash,
+        })
+    }
+
+    // function to return the number of candidates on the ballot
+    pub fn num_candidates(&self) -> usize {
+        self.candidates.len()
+    }
+
+    // function to return the number of additional contests on the ballot
+    pub fn num_contests(&self) -> usize {
+        self.contests.len()
+    }
+
+    // function to check whether ballots are accepted at the given unix time
+    pub fn is_open_at(&self, time: u64) -> bool {
+        self.opens_at <= time && time < self.closes_at
+    }
+
+    // function to check whether ballots are accepted right now
+    pub fn is_open(&self) -> bool {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        self.is_open_at(now)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // manifest with two candidates and one contest
+    const MANIFEST: &str = r#"
+        id = "test_election"
+        title = "Test Election"
+        opens_at = 100
+        closes_at = 200
+
+        [[candidates]]
+        id = "a"
+        name = "Candidate A"
+
+        [[candidates]]
+        id = "b"
+        name = "Candidate B"
+
+        [[contests]]
+        id = "q"
+        question = "Question"
+
+        [parameters]
+        contest_bits = 8
+        registry_depth = 8
+    "#;
+
+    #[test]
+    fn manifest_loads_into_an_election() {
+        let election = Election::parse(MANIFEST).unwrap();
+        assert_eq!(election.candidates, vec!["Candidate A", "Candidate B"]);
+        assert_eq!(election.contests, vec!["Question"]);
+        assert!(election.is_open_at(100) && !election.is_open_at(200));
+        assert_eq!(election.curve, Curve::Bls12_381);
+        let bn254 = Election::parse(&MANIFEST.replace("registry_depth = 8", "registry_depth = 8\ncurve = \"bn254\"")).unwrap();
+        assert_eq!(bn254.curve, Curve::Bn254);
+        assert_ne!(bn254.manifest_hash, election.manifest_hash);
+    }
+
+    #[test]
+    fn malformed_manifests_are_rejected() {
+        let duplicate = MANIFEST.replace("id = \"b\"", "id = \"a\"");
+        assert_eq!(Election::parse(&duplicate).unwrap_err(), ManifestError::DuplicateCandidate("a".to_string()));
+        let window = MANIFEST.replace("closes_at = 200", "closes_at = 50");
+        assert_eq!(Election::parse(&window).unwrap_err(), ManifestError::InvalidWindow);
+        let bits = MANIFEST.replace("contest_bits = 8", "contest_bits = 7");
+        assert_eq!(Election::parse(&bits).unwrap_err(), ManifestError::UnsupportedContestBits(7));
+        let bits = MANIFEST.replace("contest_bits = 8", "contest_bits = 64");
+        assert_eq!(Election::parse(&bits).unwrap_err(), ManifestError::UnsupportedContestBits(64));
+        assert!(matches!(Election::parse("id = 1"), Err(ManifestError::Parse(_))));
+        let curve = MANIFEST.replace("registry_depth = 8", "registry_depth = 8\ncurve = \"secp256k1\"");
+        assert!(matches!(Election::parse(&curve), Err(ManifestError::Parse(_))));
+    }
+
+    #[test]
+    fn manifest_hash_ignores_formatting_but_not_content() {
+        let election = Election::parse(MANIFEST).unwrap();
+        let reformatted = Election::parse(&MANIFEST.replace("        ", "")).unwrap();
+        let renamed = Election::parse(&MANIFEST.replace("Candidate B", "Candidate C")).unwrap();
+        assert_eq!(election.manifest_hash, reformatted.manifest_hash);
+        assert_ne!(election.manifest_hash, renamed.manifest_hash);
+    }
+}
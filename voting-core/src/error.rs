@@ -0,0 +1,95 @@
+// import types from libaries and modules
+use thiserror::Error;
+use crate::election::ManifestError;
+
+// reasons an election operation can fail, shared by every backend
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum VotingError {
+    #[error("invalid election manifest: {0}")]
+    Manifest(#[from] ManifestError), // election definition was rejected
+    #[error("proof system setup failed: {0}")]
+    SetupFailed(String), // keys or generators could not be created
+    #[error("the election is not accepting ballots")]
+    ElectionClosed, // outside the voting window or mixing has started
+    #[error("the voter has already cast a ballot in this election")]
+    DuplicateNullifier, // nullifier was already recorded
+    #[error("candidate {candidate} is not on the ballot of {num_candidates} candidates")]
+    InvalidCandidate { candidate: usize, num_candidates: usize }, // choice outside the candidate set
+    #[error("contest answers are missing or out of range")]
+    InvalidAnswers, // wrong number of answers or an answer too large
+    #[error("the ballot proof does not verify")]
+    InvalidProof, // proof rejected by the backend
+    #[error("proof generation failed: {0}")]
+    ProvingFailed(String), // the prover could not build a proof
+    #[error("serialization failed: {0}")]
+    Serialization(String), // a proof or key could not be encoded
+    #[error("voter {0} is already registered")]
+    DuplicateRegistration(String), // voter ID registered twice
+    #[error("the voter is not in the registry")]
+    NotRegistered, // no registry entry for the requested voter
+    #[error("the voter registry is full")]
+    RegistryFull, // more voters than the registry tree can hold
+    #[error("the shuffle proof does not verify")]
+    InvalidShuffle, // mixer output rejected
+    #[error("the ballots have not been mixed")]
+    NotMixed, // individual ballots are only decrypted after mixing
+    #[error("not enough valid partial decryptions")]
+    InsufficientDecryptions, // fewer than threshold verified trustee shares
+    #[error("a decrypted value is outside its expected range")]
+    DecryptionFailed, // no small discrete log for a decrypted total or ballot entry
+    #[error("the vote openings do not match the accepted commitments")]
+    InvalidOpening, // tally openings missing, repeated or forged
+    #[error("could not access key file: {0}")]
+    KeyStorage(String), // key file could not be read or written
+    #[error("the stored keys do not match the published fingerprint")]
+    KeyMismatch, // keys were replaced, corrupted or made for another election
+    #[error("contribution {0} to the setup ceremony does not verify")]
+    InvalidContribution(usize), // a participant's update or its proof was rejected
+    #[error("the aggregate proof does not verify")]
+    InvalidAggregate, // aggregate of the ballot proofs rejected
+}
+
+// voting error implementation
+impl VotingError {
+
+    // function to return a stable numeric code, used as the process exit status
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::Manifest(_) => 2,
+            Self::SetupFailed(_) => 3,
+            Self::ElectionClosed => 4,
+            Self::DuplicateNullifier => 5,
+            Self::InvalidCandidate { .. } => 6,
+            Self::InvalidAnswers => 7,
+            Self::InvalidProof => 8,
+            Self::ProvingFailed(_) => 9,
+            Self::Serialization(_) => 10,
+            Self::DuplicateRegistration(_) => 11,
+            Self::NotRegistered => 12,
+            Self::RegistryFull => 13,
+            Self::InvalidShuffle => 14,
+            Self::NotMixed => 15,
+            Self::InsufficientDecryptions => 16,
+            Self::DecryptionFailed => 17,
+            Self::InvalidOpening => 18,
+            Self::KeyStorage(_) => 19,
+            Self::KeyMismatch => 20,
+            Self::InvalidContribution(_) => 21,
+            Self::InvalidAggregate => 22,
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_errors_keep_their_message() {
+        let error = VotingError::from(ManifestError::NoCandidates);
+        assert_eq!(error.to_string(), "invalid election manifest: manifest lists no candidates");
+        assert_eq!(error.code(), 2);
+        assert_ne!(VotingError::DuplicateNullifier.code(), VotingError::InvalidProof.code());
+    }
+}
@@ -0,0 +1,8 @@
+// shared voting types and the submission engine used by every proof system backend
+pub mod backend;
+pub mod ballot;
+pub mod ballot_box;
+pub mod election;
+pub mod error;
+pub mod tally;
+pub mod voter;
@@ -0,0 +1,24 @@
+// import types from libaries and modules
+use crate::election::Election;
+
+// decrypted or opened election results
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tally {
+    pub candidates: Vec<usize>, // votes for each candidate in ballot order
+    pub contests: Vec<usize>, // sum of the answers to each contest
+}
+
+// tally implementation
+impl Tally {
+
+    // function to display the results with the names from the election
+    pub fn display(&self, election: &Election) {
+        for (candidate, count) in election.candidates.iter().zip(&self.candidates) {
+            println!("- {}: {} votes", candidate, count);
+        }
+        for (contest, total) in election.contests.iter().zip(&self.contests) {
+            println!("- {}: {}", contest, total);
+        }
+    }
+
+}
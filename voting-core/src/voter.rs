@@ -0,0 +1,59 @@
+// import types from libaries and modules
+use rand::{rngs::OsRng, RngCore};
+use sha3::{Sha3_512, Digest};
+
+// voter structure
+#[derive(Debug, Clone)]
+pub struct Voter {
+    pub id: String, // voter ID
+    key: [u8; 32], // long term secret key every backend derives its credentials from
+}
+
+// voter implementation
+impl Voter {
+
+    // function to create a new voter with a freshly generated secret key
+    pub fn new(id: String) -> Self {
+
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+
+        // constructed voter
+        Self { id, key }
+
+    }
+
+    // function to re-create a voter from an exported secret key
+    pub fn from_key(id: String, key: [u8; 32]) -> Self {
+        Self { id, key }
+    }
+
+    // function to export the secret key so the voter can be re-created later
+    pub fn export_key(&self) -> [u8; 32] {
+        self.key
+    }
+
+    // function to derive 64 uniform bytes from the key for one backend, reduced into its own field
+    pub fn derive_secret(&self, domain: &[u8]) -> [u8; 64] {
+        let mut hash = Sha3_512::new();
+        hash.update((domain.len() as u64).to_le_bytes());
+        hash.update(domain);
+        hash.update(self.key);
+        hash.finalize().into()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recreated_voter_derives_the_same_secrets() {
+        let voter = Voter::new("voter".to_string());
+        let recreated = Voter::from_key("voter".to_string(), voter.export_key());
+        assert_eq!(voter.derive_secret(b"backend"), recreated.derive_secret(b"backend"));
+        assert_ne!(voter.derive_secret(b"backend"), voter.derive_secret(b"other"));
+        assert_ne!(voter.derive_secret(b"backend"), Voter::new("voter".to_string()).derive_secret(b"backend"));
+    }
+}